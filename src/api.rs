@@ -0,0 +1,144 @@
+// Framework-agnostic request handler for serving counts over HTTP or any other transport:
+// deserialize a CountRequest, pass it to handle_request, and serialize what comes back.
+
+use std::fmt;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::generator::{analyze_shapes, generate_polycubes_single_threaded, Count};
+use crate::safe_counter::{count_fixed_polycubes, CounterConfig, SymmetryMode};
+
+// Sizes handle_request accepts; larger counts take too long to compute per request
+pub const MIN_REQUEST_SIZE: usize = 1;
+pub const MAX_REQUEST_SIZE: usize = 11;
+
+// Which shapes count as distinct, serialized as "fixed", "one-sided" or "free"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CountKind {
+    Fixed,
+    OneSided,
+    Free,
+}
+
+impl CountKind {
+    pub fn symmetry(self) -> SymmetryMode {
+        match self {
+            CountKind::Fixed => SymmetryMode::Translation,
+            CountKind::OneSided => SymmetryMode::Rotation,
+            CountKind::Free => SymmetryMode::RotationReflection,
+        }
+    }
+}
+
+// How the count is computed, serialized as "counter" or "generator"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CountAlgorithm {
+    // The counting algorithm, without materializing the shapes
+    #[default]
+    Counter,
+    // Generate every one-sided shape and derive the count from their symmetries
+    Generator,
+}
+
+// e.g. {"n": 6, "kind": "free"}; algorithm defaults to the counter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountRequest {
+    pub n: usize,
+    pub kind: CountKind,
+    #[serde(default)]
+    pub algorithm: CountAlgorithm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountResponse {
+    pub n: usize,
+    pub count: Count,
+    pub elapsed_ms: u64,
+    // Whether the count equals the known count of the same kind; None if n is not tabulated
+    pub matched_known: Option<bool>,
+}
+
+// Serialized with an "error" tag, e.g. {"error": "size-out-of-range", "n": 40, ...}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "error", rename_all = "kebab-case")]
+pub enum ApiError {
+    SizeOutOfRange { n: usize, min: usize, max: usize },
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::SizeOutOfRange { n, min, max } => write!(f, "size {} is outside {}..={}", n, min, max),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// Compute the requested count. Prints nothing and touches no cache files, so it is safe to
+// call from a server; the caller decides how to run it off the request thread.
+pub fn handle_request(req: CountRequest) -> Result<CountResponse, ApiError> {
+    if !(MIN_REQUEST_SIZE..=MAX_REQUEST_SIZE).contains(&req.n) {
+        return Err(ApiError::SizeOutOfRange { n: req.n, min: MIN_REQUEST_SIZE, max: MAX_REQUEST_SIZE });
+    }
+
+    let start = Instant::now();
+    let symmetry = req.kind.symmetry();
+
+    let count = match req.algorithm {
+        CountAlgorithm::Counter => {
+            let config = CounterConfig { show_progress: false, symmetry, ..CounterConfig::default() };
+            count_fixed_polycubes(req.n, Some(config))
+        }
+        CountAlgorithm::Generator => {
+            let n = req.n as u8;
+            let analysis = analyze_shapes(n, &generate_polycubes_single_threaded(n));
+            match req.kind {
+                CountKind::Fixed => analysis.fixed,
+                CountKind::OneSided => analysis.one_sided,
+                CountKind::Free => analysis.free,
+            }
+        }
+    };
+
+    Ok(CountResponse {
+        n: req.n,
+        count,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        matched_known: symmetry.known_count(req.n as u8).map(|known| known == count),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(json: &str) -> Result<CountResponse, ApiError> {
+        handle_request(serde_json::from_str(json).unwrap())
+    }
+
+    #[test]
+    fn requests_give_the_known_counts() {
+        let fixed = request(r#"{"n": 5, "kind": "fixed"}"#).unwrap();
+        assert_eq!((fixed.n, fixed.count, fixed.matched_known), (5, 534, Some(true)));
+
+        let free = request(r#"{"n": 6, "kind": "free", "algorithm": "generator"}"#).unwrap();
+        assert_eq!((free.count, free.matched_known), (112, Some(true)));
+        let one_sided = request(r#"{"n": 6, "kind": "one-sided", "algorithm": "counter"}"#).unwrap();
+        assert_eq!(one_sided.count, 166);
+    }
+
+    #[test]
+    fn out_of_range_sizes_are_errors() {
+        for n in [0, MAX_REQUEST_SIZE + 1] {
+            let error = handle_request(CountRequest { n, kind: CountKind::Fixed, algorithm: CountAlgorithm::Counter }).unwrap_err();
+            assert_eq!(error, ApiError::SizeOutOfRange { n, min: MIN_REQUEST_SIZE, max: MAX_REQUEST_SIZE });
+        }
+
+        let error = serde_json::to_value(request(r#"{"n": 40, "kind": "free"}"#).unwrap_err()).unwrap();
+        assert_eq!(error, serde_json::json!({"error": "size-out-of-range", "n": 40, "min": 1, "max": 11}));
+    }
+}
@@ -0,0 +1,53 @@
+use std::future::Future;
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+use crate::generator::Count;
+use crate::safe_counter::{count_polycubes, count_polycubes_with_config, CounterConfig, CounterProgress};
+
+/// Count polycubes of size n like `count_polycubes`, running the blocking work on
+/// tokio's blocking thread pool. Must be awaited from within a tokio runtime.
+pub async fn count_polycubes_async(n: usize, use_symmetry: bool) -> Count {
+    tokio::task::spawn_blocking(move || count_polycubes(n, use_symmetry))
+        .await
+        .expect("counting task panicked")
+}
+
+/// Like `count_polycubes_async`, also returning a channel of progress updates.
+///
+/// Updates are only sent by the parallel counter, once per finished starting
+/// configuration; sizes answered from known values complete without any. Console
+/// output is disabled. The channel closes when counting finishes.
+pub fn count_polycubes_async_with_progress(
+    n: usize,
+    use_symmetry: bool,
+) -> (impl Future<Output = Count>, UnboundedReceiver<CounterProgress>) {
+    let (async_sender, async_receiver) = unbounded_channel();
+
+    let future = async move {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let config = CounterConfig {
+            show_progress: false,
+            progress: Some(sender),
+            ..CounterConfig::default()
+        };
+
+        // Relay updates from the counter's std channel until it is dropped
+        let relay = tokio::task::spawn_blocking(move || {
+            for update in receiver {
+                if async_sender.send(update).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let count = tokio::task::spawn_blocking(move || count_polycubes_with_config(n, use_symmetry, Some(config)))
+            .await
+            .expect("counting task panicked");
+        let _ = relay.await;
+
+        count
+    };
+
+    (future, async_receiver)
+}
@@ -0,0 +1,210 @@
+use std::time::{Duration, Instant};
+
+use crate::generator::{generate_polycubes, Count};
+use crate::polycube_set::PolycubeSet;
+use crate::safe_counter::{count_free_polycubes, CounterConfig};
+
+// Timing of a single generator run
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    pub n: u8,
+    pub count: Count,
+    pub elapsed: Duration,
+}
+
+// Timing of the generator and the counter for the same n
+#[derive(Debug, Clone)]
+pub struct ComparisonResult {
+    pub n: u8,
+    pub generator_count: Count,
+    pub generator_time: Duration,
+    pub counter_count: Count,
+    pub counter_time: Duration,
+    pub counts_agree: bool,
+}
+
+// Heap memory held by one size's result set in each representation
+#[derive(Debug, Clone)]
+pub struct MemoryResult {
+    pub n: u8,
+    pub count: Count,
+    pub vec_bytes: usize,
+    pub packed_bytes: usize,
+}
+
+// Time generate_polycubes (without cache) for each n in min..=max
+pub fn run_benchmarks(min: u8, max: u8) -> Vec<BenchmarkResult> {
+    let mut results = Vec::new();
+
+    for n in min..=max {
+        let start = Instant::now();
+        let count = generate_polycubes(n, false).len() as Count;
+        let elapsed = start.elapsed();
+
+        results.push(BenchmarkResult { n, count, elapsed });
+    }
+
+    println!("\nBenchmark results:");
+    println!("{:>4} {:>12} {:>12}", "n", "count", "time (s)");
+    for result in &results {
+        println!("{:>4} {:>12} {:>12.3}", result.n, result.count, result.elapsed.as_secs_f64());
+    }
+
+    results
+}
+
+// Run both the generator and the counter for each n in min..=max,
+// recording both timings and flagging any count disagreement
+pub fn run_comparison_benchmarks(min: u8, max: u8) -> Vec<ComparisonResult> {
+    let mut results = Vec::new();
+
+    for n in min..=max {
+        let start = Instant::now();
+        let generator_count = generate_polycubes(n, false).len() as Count;
+        let generator_time = start.elapsed();
+
+        let config = CounterConfig {
+            show_progress: false,
+            ..CounterConfig::default()
+        };
+        let start = Instant::now();
+        let counter_count = count_free_polycubes(n as usize, Some(config));
+        let counter_time = start.elapsed();
+
+        results.push(ComparisonResult {
+            n,
+            generator_count,
+            generator_time,
+            counter_count,
+            counter_time,
+            counts_agree: generator_count == counter_count,
+        });
+    }
+
+    println!("\nGenerator vs counter:");
+    println!("{:>4} {:>12} {:>12} {:>12} {:>12}  status",
+        "n", "generated", "gen (s)", "counted", "count (s)");
+    for result in &results {
+        println!("{:>4} {:>12} {:>12.3} {:>12} {:>12.3}  {}",
+            result.n,
+            result.generator_count,
+            result.generator_time.as_secs_f64(),
+            result.counter_count,
+            result.counter_time.as_secs_f64(),
+            if result.counts_agree { "ok" } else { "MISMATCH" });
+    }
+
+    results
+}
+
+// Generate each n in min..=max and compare the heap memory of the Vec<Polycube> result
+// with the same shapes packed into a PolycubeSet
+pub fn run_memory_benchmarks(min: u8, max: u8) -> Vec<MemoryResult> {
+    let mut results = Vec::new();
+
+    for n in min..=max {
+        let polycubes = generate_polycubes(n, false);
+        let vec_bytes = PolycubeSet::vec_heap_bytes(&polycubes);
+        let packed_bytes = PolycubeSet::from(polycubes.as_slice()).heap_bytes();
+
+        results.push(MemoryResult { n, count: polycubes.len() as Count, vec_bytes, packed_bytes });
+    }
+
+    println!("\nResult set memory:");
+    println!("{:>4} {:>12} {:>14} {:>14} {:>8}", "n", "count", "Vec (bytes)", "packed (bytes)", "ratio");
+    for result in &results {
+        println!("{:>4} {:>12} {:>14} {:>14} {:>8.2}",
+            result.n,
+            result.count,
+            result.vec_bytes,
+            result.packed_bytes,
+            result.vec_bytes as f64 / result.packed_bytes.max(1) as f64);
+    }
+
+    results
+}
+
+// Time count_fixed_polycubes(n) once per thread count, returning (threads, milliseconds).
+// Each run gets its own rayon pool of that size, so the global pool is left alone. One
+// thread takes the counter's single-threaded path; more take the parallel one.
+#[cfg(feature = "parallel")]
+pub fn run_thread_scaling_benchmark(n: usize, thread_counts: &[usize]) -> Vec<(usize, u128)> {
+    let runs = time_thread_counts(n, thread_counts);
+
+    println!("\nCounter scaling for n={}:", n);
+    println!("{:>8} {:>14} {:>12} {:>8}", "threads", "count", "time (ms)", "speedup");
+    let baseline = runs.first().map_or(0, |&(_, millis, _)| millis);
+    for &(threads, millis, count) in &runs {
+        println!("{:>8} {:>14} {:>12} {:>8.2}", threads, count, millis, baseline as f64 / millis.max(1) as f64);
+    }
+    if runs.windows(2).any(|pair| pair[0].2 != pair[1].2) {
+        println!("Warning: counts differ between thread counts");
+    }
+
+    runs.into_iter().map(|(threads, millis, _)| (threads, millis)).collect()
+}
+
+// The runs of run_thread_scaling_benchmark as (threads, milliseconds, count), skipping
+// thread counts whose pool cannot be built
+#[cfg(feature = "parallel")]
+fn time_thread_counts(n: usize, thread_counts: &[usize]) -> Vec<(usize, u128, Count)> {
+    let mut runs = Vec::new();
+
+    for &threads in thread_counts {
+        let config = CounterConfig {
+            threads,
+            show_progress: false,
+            ..CounterConfig::default()
+        };
+        let pool = match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool,
+            Err(e) => {
+                println!("Skipping {} threads: {}", threads, e);
+                continue;
+            }
+        };
+
+        let start = Instant::now();
+        let count = pool.install(|| crate::safe_counter::count_fixed_polycubes(n, Some(config)));
+        runs.push((threads, start.elapsed().as_millis(), count));
+    }
+
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_agreement(min: u8, max: u8) {
+        for result in run_comparison_benchmarks(min, max) {
+            assert!(result.counts_agree, "generator found {} and counter {} for n={}",
+                result.generator_count, result.counter_count, result.n);
+        }
+    }
+
+    #[test]
+    fn counter_agrees_with_generator_small() {
+        assert_agreement(1, 7);
+    }
+
+    #[test]
+    #[ignore = "slow in debug builds"]
+    fn counter_agrees_with_generator_large() {
+        assert_agreement(8, 9);
+    }
+
+    // Every thread count, through both the single-threaded and the parallel counter,
+    // finds the same count
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn thread_counts_agree() {
+        let runs = time_thread_counts(6, &[1, 2, 4]);
+        let threads: Vec<usize> = runs.iter().map(|&(threads, _, _)| threads).collect();
+        assert_eq!(threads, [1, 2, 4]);
+        for &(threads, _, count) in &runs {
+            assert_eq!(Some(count), crate::generator::get_known_fixed_count(6), "{} threads", threads);
+        }
+        assert_eq!(run_thread_scaling_benchmark(5, &[1, 2]).len(), 2);
+    }
+}
@@ -0,0 +1,433 @@
+use std::time::{Duration, Instant};
+
+use rustc_hash::FxHashSet;
+use serde::Serialize;
+
+use polycube_generator::benchmark::{run_benchmarks, run_comparison_benchmarks, run_memory_benchmarks};
+use polycube_generator::generator::{
+    configure_thread_pool, generate_box_fillers, generate_polycubes_with_policy, generate_summary, load_from_cache,
+    self_check, verify_one_sided_count, CachePolicy, Count, VerificationReport,
+};
+use polycube_generator::polycube::{Connectivity, Polycube};
+use polycube_generator::polycube_exporter::{self, ExportFormat, SortKey};
+use polycube_generator::safe_counter::{count_polycubes_with_report, CounterConfig, SymmetryMode};
+
+// Non-interactive subcommands. None of them prompt; each returns its own exit code:
+//   0 success, 1 a count or cache check failed, 2 bad usage or an I/O error
+pub const SUBCOMMANDS: [&str; 5] = ["generate", "count", "export", "benchmark", "verify-cache"];
+
+const EXIT_OK: i32 = 0;
+const EXIT_CHECK_FAILED: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+const USAGE: &str = "\
+Usage:
+  polycube-generator generate N [--kind all|box-fillers] [--format csv|text|json]... [--sort KEY] [--no-cache|--cache-final-only] [--threads K] [--self-check] [--no-verify]
+  polycube-generator count N [--one-sided|--free|--fixed] [--threads K] [--json] [--compute] [--no-verify]
+  polycube-generator export N --format csv|text|json [--sort KEY] [--export-index I] [--no-cache|--cache-final-only] [--threads K] [--no-verify]
+  polycube-generator benchmark --max N [--min N] [--compare|--memory] [--threads K]
+  polycube-generator verify-cache N
+  polycube-generator [N] [flags]   (interactive mode)
+  polycube-generator N --json [--no-symmetry] [--threads K]   (count, printing only a JSON object)
+
+--threads K limits generation and counting to K threads (0, the default, uses every CPU)
+--one-sided (the default) counts up to rotation, --free also up to reflection, --fixed not at all
+--compute runs the counter even for sizes whose count is tabulated (n <= 18), and checks it
+--no-verify skips the comparison with the known counts";
+
+pub fn is_subcommand(arg: &str) -> bool {
+    SUBCOMMANDS.contains(&arg)
+}
+
+// Run a subcommand with the arguments that follow it and return the process exit code
+pub fn run(command: &str, args: &[String]) -> i32 {
+    let result = match command {
+        "generate" => generate(args),
+        "count" => count(args),
+        "export" => export(args),
+        "benchmark" => benchmark(args),
+        "verify-cache" => verify_cache(args),
+        _ => Err(format!("unknown subcommand {:?}", command)),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            println!("Error: {}", message);
+            println!("{}", USAGE);
+            EXIT_ERROR
+        }
+    }
+}
+
+// Parse a --sort value
+pub fn parse_sort_key(value: &str) -> Option<SortKey> {
+    match value {
+        "canonical" => Some(SortKey::Canonical),
+        "surface-area" => Some(SortKey::SurfaceArea),
+        "connectivity" => Some(SortKey::Connectivity),
+        "diameter" => Some(SortKey::Diameter),
+        "dimensionality" => Some(SortKey::Dimensionality),
+        _ => None,
+    }
+}
+
+// Parse a --format value
+fn parse_export_format(value: &str) -> Result<ExportFormat, String> {
+    match value {
+        "csv" => Ok(ExportFormat::Csv),
+        "text" => Ok(ExportFormat::Text),
+        "json" => Ok(ExportFormat::Json),
+        other => Err(format!("unsupported export format {:?} (expected csv, text or json)", other)),
+    }
+}
+
+// Arguments of one subcommand: positionals, bare switches and `--name value` options
+struct ParsedArgs {
+    positional: Vec<String>,
+    switches: FxHashSet<String>,
+    values: Vec<(String, String)>,
+}
+
+impl ParsedArgs {
+    // Split args, rejecting any flag not listed for the subcommand
+    fn parse(args: &[String], switches: &[&str], valued: &[&str]) -> Result<Self, String> {
+        let mut parsed = ParsedArgs {
+            positional: Vec::new(),
+            switches: FxHashSet::default(),
+            values: Vec::new(),
+        };
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if switches.contains(&arg.as_str()) {
+                parsed.switches.insert(arg.clone());
+            } else if valued.contains(&arg.as_str()) {
+                let value = iter.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                parsed.values.push((arg.clone(), value.clone()));
+            } else if arg.starts_with("--") {
+                return Err(format!("unknown option {}", arg));
+            } else {
+                parsed.positional.push(arg.clone());
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn has(&self, switch: &str) -> bool {
+        self.switches.contains(switch)
+    }
+
+    // Last value given for an option
+    fn value(&self, name: &str) -> Option<&str> {
+        self.all_values(name).pop()
+    }
+
+    // Every value given for a repeatable option, in order
+    fn all_values(&self, name: &str) -> Vec<&str> {
+        self.values.iter()
+            .filter(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    // The single positional size argument
+    fn size(&self) -> Result<u8, String> {
+        match self.positional.as_slice() {
+            [size] => parse_size(size),
+            [] => Err("missing size N".to_string()),
+            _ => Err(format!("unexpected arguments {:?}", &self.positional[1..])),
+        }
+    }
+
+    fn cache_policy(&self) -> CachePolicy {
+        if self.has("--no-cache") {
+            CachePolicy::None
+        } else if self.has("--cache-final-only") {
+            CachePolicy::FinalOnly
+        } else {
+            CachePolicy::All
+        }
+    }
+
+    // Apply --threads to the global thread pool, returning the thread count in use
+    fn configure_threads(&self) -> Result<usize, String> {
+        let threads = match self.value("--threads") {
+            Some(value) => parse_threads(value)?,
+            None => 0,
+        };
+        Ok(configure_thread_pool(threads))
+    }
+
+    fn sort_key(&self) -> Result<SortKey, String> {
+        match self.value("--sort") {
+            Some(value) => parse_sort_key(value).ok_or_else(|| format!("unknown sort key {:?}", value)),
+            None => Ok(SortKey::default()),
+        }
+    }
+}
+
+fn parse_size(value: &str) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(n) if (1..=18).contains(&n) => Ok(n),
+        _ => Err(format!("size must be a number from 1 to 18, got {:?}", value)),
+    }
+}
+
+// Parse a --threads value; 0 means one thread per CPU
+pub fn parse_threads(value: &str) -> Result<usize, String> {
+    value.parse::<usize>().map_err(|_| format!("--threads must be a non-negative number, got {:?}", value))
+}
+
+// generate N: generate, verify the count and optionally export
+fn generate(args: &[String]) -> Result<i32, String> {
+    let args = ParsedArgs::parse(args, &["--no-cache", "--cache-final-only", "--self-check", "--no-verify"], &["--kind", "--format", "--sort", "--threads"])?;
+    let n = args.size()?;
+    args.configure_threads()?;
+    let sort_key = args.sort_key()?;
+    let verify = !args.has("--no-verify");
+    let formats = args.all_values("--format").into_iter().map(parse_export_format).collect::<Result<Vec<_>, _>>()?;
+
+    let start_time = Instant::now();
+    let (polycubes, verification) = match args.value("--kind").unwrap_or("all") {
+        "all" => {
+            let polycubes = generate_polycubes_with_policy(n, args.cache_policy());
+            let verification = verify.then(|| verify_one_sided_count(n, polycubes.len() as Count)).flatten();
+            (polycubes, verification)
+        }
+        "box-fillers" => (generate_box_fillers(n), None),
+        other => return Err(format!("unknown kind {:?} (expected all or box-fillers)", other)),
+    };
+
+    println!("Generated {} polycubes of size {}", polycubes.len(), n);
+    println!("Time taken: {:.2} seconds", start_time.elapsed().as_secs_f32());
+
+    if let Some(report) = &verification {
+        report.print(n);
+    }
+    if args.has("--self-check") {
+        match self_check(&polycubes, n) {
+            Ok(()) => println!("Self-check passed"),
+            Err(e) => {
+                println!("Self-check failed: {}", e);
+                return Ok(EXIT_CHECK_FAILED);
+            }
+        }
+    }
+    generate_summary(&polycubes);
+
+    // The count was already checked and printed above
+    if !formats.is_empty() {
+        if let Err(e) = export_polycubes(&polycubes, n, &formats, sort_key, false) {
+            println!("Error exporting: {}", e);
+            return Ok(EXIT_ERROR);
+        }
+    }
+
+    Ok(match verification {
+        Some(report) if !report.matches() => EXIT_CHECK_FAILED,
+        _ => EXIT_OK,
+    })
+}
+
+// count N: count without generating, verifying against the known table. Tabulated sizes
+// are answered from the table unless --compute is given. With --json the result is printed
+// as a single JSON object and nothing else.
+fn count(args: &[String]) -> Result<i32, String> {
+    let args = ParsedArgs::parse(args, &["--one-sided", "--free", "--fixed", "--json", "--compute", "--no-verify"], &["--threads"])?;
+    let n = args.size()?;
+    let json = args.has("--json");
+
+    let kinds = ["--one-sided", "--free", "--fixed"].iter().filter(|flag| args.has(flag)).count();
+    if kinds > 1 {
+        return Err("--one-sided, --free and --fixed are mutually exclusive".to_string());
+    }
+    // Rotations are applied through use_symmetry; free counts ask the counter for the
+    // reflections as well
+    let (use_symmetry, symmetry) = if args.has("--fixed") {
+        (false, SymmetryMode::Translation)
+    } else if args.has("--free") {
+        (false, SymmetryMode::RotationReflection)
+    } else {
+        (true, SymmetryMode::Rotation)
+    };
+
+    let config = CounterConfig {
+        threads: args.configure_threads()?,
+        show_progress: !json,
+        force_compute: args.has("--compute"),
+        verify: !args.has("--no-verify"),
+        symmetry,
+        ..CounterConfig::default()
+    };
+
+    let start_time = Instant::now();
+    let (count, verification) = count_polycubes_with_report(n as usize, use_symmetry, Some(config));
+    let code = match verification {
+        Some(report) if !report.matches() => EXIT_CHECK_FAILED,
+        _ => EXIT_OK,
+    };
+
+    if json {
+        print_count_json(n, symmetry, count, verification.as_ref(), start_time.elapsed());
+        return Ok(code);
+    }
+
+    println!("Count of {} polycubes of size {}: {}", symmetry.kind(), n, count);
+    println!("Time taken: {:.2} seconds", start_time.elapsed().as_secs_f32());
+
+    match verification {
+        Some(report) => report.print(n),
+        None if !args.has("--compute") && symmetry.known_count(n).is_some() => {
+            println!("Read from the known table (--compute runs the counter and checks it)");
+        }
+        None => {}
+    }
+    Ok(code)
+}
+
+// Result of a count as printed by --json, e.g.
+//   {"n":5,"kind":"one-sided","count":29,"expected":29,"matched":true,"elapsed_ms":3}
+// kind is SymmetryMode::kind of the counted symmetry; expected and matched are null when
+// n is not tabulated.
+#[derive(Serialize)]
+struct CountSummary {
+    n: u8,
+    kind: &'static str,
+    count: Count,
+    expected: Option<Count>,
+    matched: Option<bool>,
+    elapsed_ms: u64,
+}
+
+// Print a count as one line of JSON on stdout
+pub fn print_count_json(n: u8, symmetry: SymmetryMode, count: Count, verification: Option<&VerificationReport>, elapsed: Duration) {
+    let summary = CountSummary {
+        n,
+        kind: symmetry.kind(),
+        count,
+        expected: verification.map(|report| report.expected),
+        matched: verification.map(|report| report.matches()),
+        elapsed_ms: elapsed.as_millis() as u64,
+    };
+    println!("{}", serde_json::to_string(&summary).expect("count summary serializes"));
+}
+
+// export N --format F: generate (using the cache) and export in one format. With
+// --export-index I, only the I-th generated shape (counting from 0) is exported.
+fn export(args: &[String]) -> Result<i32, String> {
+    let args = ParsedArgs::parse(args, &["--no-cache", "--cache-final-only", "--no-verify"], &["--format", "--sort", "--export-index", "--threads"])?;
+    let n = args.size()?;
+    let sort_key = args.sort_key()?;
+    let format = parse_export_format(args.value("--format").ok_or("missing --format")?)?;
+    let index = match args.value("--export-index") {
+        Some(value) => Some(value.parse::<usize>().map_err(|_| format!("--export-index must be a number, got {:?}", value))?),
+        None => None,
+    };
+    args.configure_threads()?;
+
+    let polycubes = generate_polycubes_with_policy(n, args.cache_policy());
+
+    let result = match index {
+        Some(index) => {
+            let polycube = polycubes.get(index).ok_or_else(|| format!(
+                "--export-index {} is out of range: there are {} polycubes of size {} (indices 0 to {})",
+                index, polycubes.len(), n, polycubes.len() - 1))?;
+            let path = format!("polycube_{}_{}.{}", n, index, format.extension());
+            polycube_exporter::export_single(polycube, format, &path).map(|()| {
+                println!("Wrote {}", path);
+                None
+            })
+        }
+        None => export_polycubes(&polycubes, n, &[format], sort_key, !args.has("--no-verify")),
+    };
+
+    match result {
+        Ok(Some(report)) => {
+            report.print(n);
+            Ok(if report.matches() { EXIT_OK } else { EXIT_CHECK_FAILED })
+        }
+        Ok(None) => Ok(EXIT_OK),
+        Err(e) => {
+            println!("Error exporting: {}", e);
+            Ok(EXIT_ERROR)
+        }
+    }
+}
+
+// benchmark --max N: time generation (and optionally the counter) for min..=max,
+// or with --memory compare the heap size of the result set representations
+fn benchmark(args: &[String]) -> Result<i32, String> {
+    let args = ParsedArgs::parse(args, &["--compare", "--memory"], &["--min", "--max", "--threads"])?;
+    if !args.positional.is_empty() {
+        return Err(format!("unexpected arguments {:?}", args.positional));
+    }
+    args.configure_threads()?;
+
+    let max = parse_size(args.value("--max").ok_or("missing --max")?)?;
+    let min = match args.value("--min") {
+        Some(value) => parse_size(value)?,
+        None => 1,
+    };
+    if min > max {
+        return Err(format!("--min {} is greater than --max {}", min, max));
+    }
+
+    if args.has("--compare") && args.has("--memory") {
+        return Err("--compare and --memory are mutually exclusive".to_string());
+    }
+
+    if args.has("--memory") {
+        run_memory_benchmarks(min, max);
+        Ok(EXIT_OK)
+    } else if args.has("--compare") {
+        let results = run_comparison_benchmarks(min, max);
+        Ok(if results.iter().all(|result| result.counts_agree) { EXIT_OK } else { EXIT_CHECK_FAILED })
+    } else {
+        run_benchmarks(min, max);
+        Ok(EXIT_OK)
+    }
+}
+
+// verify-cache N: check that cubes_N.zst holds exactly the known number of distinct,
+// connected shapes of size N
+fn verify_cache(args: &[String]) -> Result<i32, String> {
+    let args = ParsedArgs::parse(args, &[], &[])?;
+    let n = args.size()?;
+
+    let path = format!("cubes_{}.zst", n);
+    let polycubes = match load_from_cache(&path) {
+        Ok(polycubes) => polycubes,
+        Err(e) => {
+            println!("Error reading {}: {}", path, e);
+            return Ok(EXIT_ERROR);
+        }
+    };
+    println!("Loaded {} shapes from {}", polycubes.len(), path);
+
+    let wrong_size = polycubes.iter().filter(|p| p.cubes.len() != n as usize).count();
+    let disconnected = polycubes.iter().filter(|p| !p.is_connected(Connectivity::Face)).count();
+    let mut hashes = FxHashSet::default();
+    let duplicates = polycubes.iter().filter(|p| !hashes.insert(Polycube::get_canonical_hash(p))).count();
+
+    println!("Wrong size: {}", wrong_size);
+    println!("Disconnected: {}", disconnected);
+    println!("Duplicates: {}", duplicates);
+
+    let mut ok = wrong_size == 0 && disconnected == 0 && duplicates == 0;
+    if let Some(report) = verify_one_sided_count(n, polycubes.len() as Count) {
+        report.print(n);
+        ok &= report.matches();
+    }
+
+    Ok(if ok { EXIT_OK } else { EXIT_CHECK_FAILED })
+}
+
+fn export_polycubes(polycubes: &[Polycube], n: u8, formats: &[ExportFormat], sort_key: SortKey, verify: bool) -> std::io::Result<Option<VerificationReport>> {
+    let report = polycube_exporter::export_all(polycubes, n, ".", formats, sort_key, verify)?;
+    for path in &report.paths {
+        println!("Wrote {}", path);
+    }
+    Ok(report.verification)
+}
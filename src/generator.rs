@@ -1,21 +1,203 @@
-use std::fs::File;
-use std::io::{Write, Read};
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::sync::{atomic::{AtomicUsize, Ordering}, Mutex};
 
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
+use serde::{Serialize, Deserialize};
 
-use crate::polycube::Polycube;
+use crate::polycube::{Axis, Connectivity, Polycube, Pos};
+use crate::progress::LayerProgress;
+use crate::rotation::{all_rotations, CanonicalKey};
+
+// Controls which sizes are read from and written to the cubes_{n}.zst cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    // Never touch the cache
+    None,
+    // Only cache the requested size; intermediate sizes are generated in memory
+    FinalOnly,
+    // Cache every size reached during the recursion
+    #[default]
+    All,
+}
+
+impl CachePolicy {
+    // Policy to use for the smaller base sizes generated during recursion
+    fn for_base(self) -> Self {
+        match self {
+            CachePolicy::All => CachePolicy::All,
+            CachePolicy::FinalOnly | CachePolicy::None => CachePolicy::None,
+        }
+    }
+}
+
+// Where the generator keeps the canonical hashes of the shapes found so far in a layer
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DedupBackend {
+    // A hash set in memory: fastest, but grows with the number of shapes
+    #[default]
+    InMemory,
+    // An open-addressing table in a file at this path, sized for the layer and deleted
+    // once the layer is done. Memory stays bounded at the cost of a read per lookup.
+    OnDisk(PathBuf),
+}
+
+// Options for generate_polycubes_with_config
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GeneratorConfig {
+    pub cache_policy: CachePolicy,
+    pub dedup_backend: DedupBackend,
+}
+
+// Errors from reading and writing the polycube cache
+#[derive(Debug)]
+pub enum GeneratorError {
+    // The cache file could not be opened or created (e.g. it is missing)
+    Io(io::Error),
+    // A polycube could not be encoded or decoded with bincode
+    Serialize(bincode::Error),
+    // The zstd stream failed while compressing or decompressing, including I/O errors
+    // such as a full disk that surface through it
+    Compression(io::Error),
+    // The decompressed data is not a valid cache
+    Corrupt(String),
+    // The seed passed to generate_containing is empty or not face-connected
+    InvalidSeed,
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorError::Io(e) => write!(f, "I/O error: {}", e),
+            GeneratorError::Serialize(e) => write!(f, "serialization error: {}", e),
+            GeneratorError::Compression(e) => write!(f, "compression error: {}", e),
+            GeneratorError::Corrupt(message) => write!(f, "corrupt cache: {}", message),
+            GeneratorError::InvalidSeed => write!(f, "seed shape must be a non-empty, face-connected polycube"),
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GeneratorError::Io(e) | GeneratorError::Compression(e) => Some(e),
+            GeneratorError::Serialize(e) => Some(e),
+            GeneratorError::Corrupt(_) | GeneratorError::InvalidSeed => None,
+        }
+    }
+}
+
+impl From<bincode::Error> for GeneratorError {
+    fn from(e: bincode::Error) -> Self {
+        GeneratorError::Serialize(e)
+    }
+}
+
+// Work done by the generator across the sizes it expanded. Sizes loaded from the cache
+// (and sizes 1 and 2, which are built directly) contribute nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GenerationStats {
+    // Shapes produced by adding one cube to a base shape
+    pub candidates: usize,
+    // Candidates rejected because they were not face-connected
+    pub disconnected: usize,
+    // Candidates rejected as rotations of a shape already found
+    pub duplicates: usize,
+}
+
+// Shared counters behind GenerationStats; workers add their local totals once per unit of work
+#[derive(Default)]
+struct StatsCounters {
+    candidates: AtomicUsize,
+    disconnected: AtomicUsize,
+    duplicates: AtomicUsize,
+}
+
+impl StatsCounters {
+    fn record(&self, local: &GenerationStats) {
+        self.candidates.fetch_add(local.candidates, Ordering::Relaxed);
+        self.disconnected.fetch_add(local.disconnected, Ordering::Relaxed);
+        self.duplicates.fetch_add(local.duplicates, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> GenerationStats {
+        GenerationStats {
+            candidates: self.candidates.load(Ordering::Relaxed),
+            disconnected: self.disconnected.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// Size rayon's global thread pool, which runs generation and the parallel counter.
+// 0 means one thread per CPU. The global pool can only be built once, so if it already
+// exists it is kept as is. Returns the number of threads the pool actually has.
+#[cfg(feature = "parallel")]
+pub fn configure_thread_pool(threads: usize) -> usize {
+    let threads = if threads == 0 { num_cpus::get() } else { threads };
+
+    if rayon::ThreadPoolBuilder::new().num_threads(threads).build_global().is_err()
+        && rayon::current_num_threads() != threads
+    {
+        println!("Thread pool already started with {} threads; ignoring request for {}",
+            rayon::current_num_threads(), threads);
+    }
+
+    rayon::current_num_threads()
+}
+
+// Without the parallel feature everything runs on the calling thread
+#[cfg(not(feature = "parallel"))]
+pub fn configure_thread_pool(_threads: usize) -> usize {
+    1
+}
 
 // Generate all polycubes of size n
 pub fn generate_polycubes(n: u8, use_cache: bool) -> Vec<Polycube> {
+    let policy = if use_cache { CachePolicy::All } else { CachePolicy::None };
+    generate_polycubes_with_policy(n, policy)
+}
+
+// Generate all polycubes of size n, caching according to the given policy
+pub fn generate_polycubes_with_policy(n: u8, cache_policy: CachePolicy) -> Vec<Polycube> {
+    generate_counted(n, cache_policy, &DedupBackend::InMemory, &StatsCounters::default())
+        .expect("in-memory generation does not fail")
+}
+
+// Generate all polycubes of size n with the given cache policy and dedup backend. Only
+// the on-disk backend can fail, when its table file cannot be created, read or written.
+pub fn generate_polycubes_with_config(n: u8, config: &GeneratorConfig) -> Result<Vec<Polycube>, GeneratorError> {
+    generate_counted(n, config.cache_policy, &config.dedup_backend, &StatsCounters::default())
+}
+
+// Generate all polycubes of size n, also returning how much work the generation took
+pub fn generate_polycubes_with_stats(n: u8, cache_policy: CachePolicy) -> (Vec<Polycube>, GenerationStats) {
+    let counters = StatsCounters::default();
+    let polycubes = generate_counted(n, cache_policy, &DedupBackend::InMemory, &counters)
+        .expect("in-memory generation does not fail");
+    (polycubes, counters.snapshot())
+}
+
+// Recursive step of generate_polycubes_with_policy, accumulating into counters
+fn generate_counted(
+    n: u8,
+    cache_policy: CachePolicy,
+    dedup_backend: &DedupBackend,
+    counters: &StatsCounters,
+) -> Result<Vec<Polycube>, GeneratorError> {
+    let use_cache = cache_policy != CachePolicy::None;
+    
     if n < 1 {
-        return Vec::new();
+        return Ok(Vec::new());
     } else if n == 1 {
-        return vec![Polycube::unit_cube()];
+        return Ok(vec![Polycube::unit_cube()]);
     } else if n == 2 {
-        return vec![Polycube::domino()];
+        return Ok(vec![Polycube::domino()]);
     }
 
     // Check cache file
@@ -25,7 +207,7 @@ pub fn generate_polycubes(n: u8, use_cache: bool) -> Vec<Polycube> {
         match load_from_cache(&cache_path) {
             Ok(polycubes) => {
                 println!("Loaded {} shapes", polycubes.len());
-                return polycubes;
+                return Ok(polycubes);
             }
             Err(e) => {
                 println!("Error loading cache: {}", e);
@@ -34,105 +216,949 @@ pub fn generate_polycubes(n: u8, use_cache: bool) -> Vec<Polycube> {
     }
 
     // Get base polycubes (n-1)
-    let base_cubes = generate_polycubes(n - 1, use_cache);
+    let base_cubes = generate_counted(n - 1, cache_policy.for_base(), dedup_backend, counters)?;
+    
+    let polycubes = match dedup_backend {
+        DedupBackend::InMemory => expand_layer_counted(&base_cubes, Connectivity::Face, counters),
+        DedupBackend::OnDisk(path) => {
+            let tasks = expansion_tasks(&base_cubes, Connectivity::Face);
+            let unique_hashes = DiskHashSet::create(path, tasks.len()).map_err(GeneratorError::Io)?;
+            let polycubes = expand_tasks(&base_cubes, &tasks, Connectivity::Face, &unique_hashes, counters);
+            unique_hashes.finish().map_err(GeneratorError::Io)?;
+            polycubes
+        }
+    };
+    
+    println!("Found {} unique polycubes", polycubes.len());
+    
+    // Cache results
+    if use_cache {
+        println!("Saving to cache...");
+        match save_to_cache(&polycubes, &cache_path) {
+            Ok(_) => println!("Saved to cache successfully"),
+            Err(e) => println!("Error saving to cache: {}", e)
+        }
+    }
+    
+    Ok(polycubes)
+}
+
+// Generate all polycubes of size n by loading cubes_{base_n}.zst and expanding it
+// layer by layer. Unlike generate_polycubes, no other cache file is read or written,
+// and a missing base cache is an error rather than a reason to regenerate.
+pub fn generate_from_cached_base(n: u8, base_n: u8) -> Result<Vec<Polycube>, GeneratorError> {
+    if base_n < 1 || base_n > n {
+        return Err(GeneratorError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("base size {} must be between 1 and {}", base_n, n))));
+    }
+
+    let cache_path = format!("cubes_{}.zst", base_n);
+    if !Path::new(&cache_path).exists() {
+        return Err(GeneratorError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no cache for n={} at {}", base_n, cache_path))));
+    }
+
+    println!("Loading polycubes n={} from cache", base_n);
+    let mut layer = load_from_cache(&cache_path)?;
+    println!("Loaded {} shapes", layer.len());
+
+    for _ in base_n..n {
+        layer = expand_layer(&layer);
+    }
+
+    Ok(layer)
+}
+
+// Generate all shapes of n cubes that are connected under the given connectivity, one per
+// rotation class. Face connectivity gives the usual polycubes and uses the cache like
+// generate_polycubes; the others are generated in memory from a single cube.
+pub fn generate_polycubes_with_connectivity(n: u8, connectivity: Connectivity) -> Vec<Polycube> {
+    if connectivity == Connectivity::Face {
+        return generate_polycubes_with_policy(n, CachePolicy::default());
+    }
+    if n < 1 {
+        return Vec::new();
+    }
+
+    let mut layer = vec![Polycube::unit_cube()];
+    for _ in 1..n {
+        layer = expand_layer_counted(&layer, connectivity, &StatsCounters::default());
+    }
+    layer
+}
+
+// Generate all polycubes of size n on the calling thread, without printing or touching the
+// cache: for targets without threads, stdout or a filesystem, such as wasm. Practical for
+// small n only.
+pub fn generate_polycubes_single_threaded(n: u8) -> Vec<Polycube> {
+    if n < 1 {
+        return Vec::new();
+    }
+    
+    let counters = StatsCounters::default();
+    let mut layer = vec![Polycube::unit_cube()];
+    for _ in 1..n {
+        let unique_hashes = ShardedHashSet::default();
+        layer = layer.iter()
+            .flat_map(|base_cube| expand_base(base_cube, Connectivity::Face, &unique_hashes, &counters))
+            .collect();
+    }
+    layer
+}
+
+// Generate the polycubes of size n for which `accept` holds. The predicate sees each
+// unique size-n shape once, after generation; use generate_filtered_pruned to also cut
+// branches early. Works in memory without the cache.
+pub fn generate_filtered<A>(n: u8, accept: A) -> Vec<Polycube>
+where
+    A: Fn(&Polycube) -> bool + Sync,
+{
+    generate_filtered_pruned(n, |_| true, accept)
+}
+
+// Generate the polycubes of size n whose canonical_key is not in `known`, e.g. the shapes
+// missing from a partial earlier run. Works in memory without the cache.
+pub fn generate_polycubes_excluding(n: u8, known: &FxHashSet<CanonicalKey>) -> Vec<Polycube> {
+    generate_filtered(n, |polycube| !known.contains(&polycube.canonical_key()))
+}
+
+// Check whether at least `threshold` polycubes of size n satisfy `predicate`, e.g. "are
+// there more than 1000 flat octacubes?". The smaller sizes are generated in full, but the
+// last layer stops as soon as enough matches are found. Works in memory without the cache.
+pub fn count_at_least<P>(n: u8, threshold: usize, predicate: P) -> bool
+where
+    P: Fn(&Polycube) -> bool + Sync,
+{
+    if threshold == 0 {
+        return true;
+    }
+    if n < 1 {
+        return false;
+    }
+    if n == 1 {
+        return threshold == 1 && predicate(&Polycube::unit_cube());
+    }
+    
+    let mut base_cubes = vec![Polycube::unit_cube()];
+    for _ in 2..n {
+        base_cubes = expand_layer(&base_cubes);
+    }
+    
+    // Each match claims a number; the one that reaches the threshold stops the search
+    let tasks = expansion_tasks(&base_cubes, Connectivity::Face);
+    let unique_hashes = ShardedHashSet::default();
+    let found = AtomicUsize::new(0);
+    maybe_par_iter!(tasks).any(|&(index, position)| {
+        let mut stats = GenerationStats::default();
+        expand_candidate(&base_cubes[index], position, Connectivity::Face, &unique_hashes, &mut stats)
+            .is_some_and(|child| predicate(&child) && found.fetch_add(1, Ordering::Relaxed) + 1 >= threshold)
+    })
+}
+
+// generate_filtered with a pruning predicate: `keep` is applied to the shapes of every
+// size below n, and rejected shapes are not grown further. Every size-n shape is grown
+// from some shape one cube smaller, so nothing is lost as long as `keep` holds for all
+// connected parts of any shape `accept` should see (e.g. is_flat or a bounding box limit,
+// since removing cubes never breaks either). `accept` then decides which size-n shapes
+// are returned.
+pub fn generate_filtered_pruned<K, A>(n: u8, keep: K, accept: A) -> Vec<Polycube>
+where
+    K: Fn(&Polycube) -> bool + Sync,
+    A: Fn(&Polycube) -> bool + Sync,
+{
+    if n < 1 {
+        return Vec::new();
+    }
+    
+    let mut layer = vec![Polycube::unit_cube()];
+    for _ in 1..n {
+        layer = maybe_par_iter!(layer).filter(|polycube| keep(polycube)).cloned().collect();
+        layer = expand_layer(&layer);
+    }
+    
+    maybe_par_iter!(layer).filter(|polycube| accept(polycube)).cloned().collect()
+}
+
+// Generate the polycubes of size n with an orientation whose shadow along `axis` is exactly
+// `silhouette` (cells as Axis::project gives them, at any offset), one per rotation class,
+// each turned to that orientation. Shapes whose shadow fits in the silhouette in no
+// orientation are not grown further, since adding cubes only enlarges a shadow.
+pub fn generate_with_projection(n: u8, axis: Axis, silhouette: &[(i8, i8)]) -> Vec<Polycube> {
+    let Some(target) = normalized_cells(silhouette.iter().copied()) else {
+        return Vec::new();
+    };
+    let shadow = |polycube: &Polycube| -> FxHashSet<(i8, i8)> {
+        polycube.cubes.iter().map(|&pos| axis.project(pos)).collect()
+    };
+    
+    let fits = |polycube: &Polycube| all_rotations(polycube).iter()
+        .any(|rotated| fits_within(&shadow(rotated), &target));
+    
+    generate_filtered_pruned(n, fits, |_| true).into_iter()
+        .filter_map(|polycube| all_rotations(&polycube).into_iter().find(|rotated| shadow(rotated) == target))
+        .collect()
+}
+
+// Cells shifted so the smallest coordinates are 0; None if there are none
+fn normalized_cells(cells: impl Iterator<Item = (i8, i8)> + Clone) -> Option<FxHashSet<(i8, i8)>> {
+    let min_u = cells.clone().map(|(u, _)| u).min()?;
+    let min_v = cells.clone().map(|(_, v)| v).min()?;
+    Some(cells.map(|(u, v)| (u - min_u, v - min_v)).collect())
+}
+
+// Check whether some translation of `cells` (normalized) lies inside `region` (normalized)
+fn fits_within(cells: &FxHashSet<(i8, i8)>, region: &FxHashSet<(i8, i8)>) -> bool {
+    let extent = |set: &FxHashSet<(i8, i8)>| set.iter()
+        .fold((0, 0), |(u, v), &(cu, cv)| (u.max(cu), v.max(cv)));
+    let (cells_u, cells_v) = extent(cells);
+    let (region_u, region_v) = extent(region);
+    
+    (0..=region_u - cells_u).any(|du| (0..=region_v - cells_v).any(|dv| {
+        cells.iter().all(|&(u, v)| region.contains(&(u + du, v + dv)))
+    }))
+}
+
+// Grow every base polycube by one cube, keeping one representative per rotation class
+pub fn expand_layer(base_cubes: &[Polycube]) -> Vec<Polycube> {
+    expand_layer_counted(base_cubes, Connectivity::Face, &StatsCounters::default())
+}
+
+// expand_layer under any connectivity, accumulating into counters
+fn expand_layer_counted(base_cubes: &[Polycube], connectivity: Connectivity, counters: &StatsCounters) -> Vec<Polycube> {
+    let tasks = expansion_tasks(base_cubes, connectivity);
     
     // Empty set of unique hash codes for fast checking
-    let unique_hashes = Mutex::new(FxHashSet::default());
+    let unique_hashes = ShardedHashSet::default();
     
-    let total = base_cubes.len();
-    println!("Processing {} base polycubes of size {}", total, n-1);
+    expand_tasks(base_cubes, &tasks, connectivity, &unique_hashes, counters)
+}
+
+// One task per (base, expansion position) rather than per base: bases differ widely in
+// how many children they have, and finer tasks let idle threads steal the remainder
+fn expansion_tasks(base_cubes: &[Polycube], connectivity: Connectivity) -> Vec<(usize, Pos)> {
+    maybe_par_iter!(base_cubes).enumerate()
+        .flat_map(|(index, base_cube)| {
+            base_cube.get_expansion_positions(connectivity).into_iter()
+                .map(|position| (index, position))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// Run the tasks from expansion_tasks, keeping the children not yet in unique_hashes
+fn expand_tasks(
+    base_cubes: &[Polycube],
+    tasks: &[(usize, Pos)],
+    connectivity: Connectivity,
+    unique_hashes: &impl DedupSet,
+    counters: &StatsCounters,
+) -> Vec<Polycube> {
+    let n = base_cubes.first().map_or(1, |p| p.cubes.len() + 1);
+    
+    println!("Processing {} base polycubes of size {}", base_cubes.len(), n - 1);
     
     // Use rayon for parallel processing
-    let progress = AtomicUsize::new(0);
+    let progress = LayerProgress::new(n, 0, tasks.len());
     
     // Generate new shapes in parallel
-    let results: Vec<_> = base_cubes.par_iter().flat_map(|base_cube| {
-        // Get expansion positions
-        let positions = base_cube.get_expansion_positions();
-        let mut local_polycubes = Vec::new();
-        
-        for position in positions {
-            // Create expanded shape
-            let expanded_shape = base_cube.expand(position);
-            
-            // Skip if not face-connected
-            if !expanded_shape.is_face_connected() {
+    let results: Vec<_> = maybe_par_iter!(tasks).filter_map(|&(index, position)| {
+        let mut stats = GenerationStats::default();
+        let child = expand_candidate(&base_cubes[index], position, connectivity, unique_hashes, &mut stats);
+        counters.record(&stats);
+        progress.inc();
+        child
+    }).collect();
+    
+    progress.finish();
+    
+    results
+}
+
+// Generate all polycubes of size n that contain the seed shape (in some orientation).
+// Growth starts from the seed instead of a single cube; every such shape is reachable
+// because it always has a removable cube outside the seed. An empty or disconnected seed
+// is an InvalidSeed error; a seed larger than n gives no shapes.
+pub fn generate_containing(n: u8, seed: &Polycube) -> Result<Vec<Polycube>, GeneratorError> {
+    if seed.cubes.is_empty() || !seed.is_connected(Connectivity::Face) {
+        return Err(GeneratorError::InvalidSeed);
+    }
+    
+    if (n as usize) < seed.cubes.len() {
+        return Ok(Vec::new());
+    }
+    
+    let mut layer = vec![seed.normalize()];
+    for _ in seed.cubes.len()..n as usize {
+        layer = expand_layer(&layer);
+    }
+    
+    Ok(layer)
+}
+
+// Generate the polycubes of size n that fill their bounding box. These are exactly the
+// a×b×c boxes with a*b*c = n, one per unordered factorization, so they are built
+// directly rather than filtered out of a full generation.
+pub fn generate_box_fillers(n: u8) -> Vec<Polycube> {
+    let n = n as usize;
+    let mut boxes = Vec::new();
+    
+    // Enumerate a <= b <= c with a * b * c = n
+    for a in (1..=n).take_while(|a| a * a * a <= n) {
+        for b in (a..=n).take_while(|b| a * b * b <= n) {
+            if !n.is_multiple_of(a * b) {
                 continue;
             }
+            let c = n / (a * b);
             
-            // Normalize
-            let normalized = expanded_shape.normalize();
-            
-            // Get canonical hash for uniqueness check (much faster than string representation)
-            let canonical_hash = normalized.get_canonical_hash();
-            
-            // Try to add to global uniqueness set
-            let mut unique_hashes_guard = unique_hashes.lock().unwrap();
-            if unique_hashes_guard.insert(canonical_hash) {
-                local_polycubes.push(normalized);
+            let mut cubes = Vec::with_capacity(n);
+            for x in 0..a {
+                for y in 0..b {
+                    for z in 0..c {
+                        cubes.push(Pos::new(x as i8, y as i8, z as i8));
+                    }
+                }
             }
+            boxes.push(Polycube::new(cubes));
         }
-        
-        // Update progress
-        let idx = progress.fetch_add(1, Ordering::SeqCst);
-        if idx % 100 == 0 || idx == total - 1 {
-            print!("\rGenerating polycubes n={}: {:.1}%", n, (idx as f32 / total as f32) * 100.0);
-            let _ = std::io::stdout().flush();
+    }
+    
+    boxes
+}
+
+// Generate all polycubes of size n, streaming each new shape straight to a cache file
+// instead of collecting them. Only the base layer (n-1) and the set of canonical hashes
+// are held in memory. Returns the number of shapes written.
+pub fn generate_to_cache(n: u8, path: &str, cache_policy: CachePolicy) -> Result<u64, GeneratorError> {
+    let mut encoder = create_cache_encoder(path)?;
+    write_cache_header(&mut encoder)?;
+    
+    let config = GeneratorConfig { cache_policy, ..GeneratorConfig::default() };
+    let written = generate_streaming(n, &config, |polycube| write_cache_record(&mut encoder, polycube))?;
+    encoder.finish().map_err(GeneratorError::Compression)?;
+    
+    println!("Streamed {} unique polycubes to {}", written, path);
+    Ok(written)
+}
+
+// Generate all polycubes of size n, handing each new shape to `sink` as soon as it is
+// found instead of collecting them. Only the base layer (n-1) and the set of canonical
+// hashes (in memory or on disk, per config.dedup_backend) are held. `sink` runs on a
+// single writer thread, so it may write to a file in order; its first error stops the
+// run and is returned. Returns the number of shapes passed to `sink`.
+pub fn generate_streaming<F>(n: u8, config: &GeneratorConfig, mut sink: F) -> Result<u64, GeneratorError>
+where
+    F: FnMut(&Polycube) -> Result<(), GeneratorError> + Send,
+{
+    // Sizes 0 and 1 have no base layer to expand
+    if n <= 1 {
+        if n == 1 {
+            sink(&Polycube::unit_cube())?;
+            return Ok(1);
+        }
+        return Ok(0);
+    }
+    
+    let counters = StatsCounters::default();
+    let base_cubes = generate_counted(n - 1, config.cache_policy.for_base(), &config.dedup_backend, &counters)?;
+    
+    match &config.dedup_backend {
+        DedupBackend::InMemory => stream_layer(&base_cubes, &ShardedHashSet::default(), &counters, sink),
+        DedupBackend::OnDisk(path) => {
+            let capacity = base_cubes.iter()
+                .map(|base_cube| base_cube.expansion_position_count(Connectivity::Face))
+                .sum();
+            let unique_hashes = DiskHashSet::create(path, capacity).map_err(GeneratorError::Io)?;
+            let written = stream_layer(&base_cubes, &unique_hashes, &counters, sink)?;
+            unique_hashes.finish().map_err(GeneratorError::Io)?;
+            Ok(written)
         }
+    }
+}
+
+// Expand base_cubes on the worker threads and pass each new child to `sink` on a writer thread
+fn stream_layer<F>(
+    base_cubes: &[Polycube],
+    unique_hashes: &impl DedupSet,
+    counters: &StatsCounters,
+    mut sink: F,
+) -> Result<u64, GeneratorError>
+where
+    F: FnMut(&Polycube) -> Result<(), GeneratorError> + Send,
+{
+    let n = base_cubes.first().map_or(1, |p| p.cubes.len() + 1);
+    let total = base_cubes.len();
+    println!("Processing {} base polycubes of size {}", total, n - 1);
+    let progress = LayerProgress::new(n, 0, total);
+    
+    // A bounded channel keeps the writer from falling arbitrarily far behind the workers
+    let (sender, receiver) = std::sync::mpsc::sync_channel::<Polycube>(4096);
+    
+    let written = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || -> Result<u64, GeneratorError> {
+            let mut count = 0;
+            for polycube in receiver {
+                sink(&polycube)?;
+                count += 1;
+            }
+            Ok(count)
+        });
         
-        local_polycubes
-    }).collect();
+        maybe_par_iter!(base_cubes).for_each(|base_cube| {
+            for polycube in expand_base(base_cube, Connectivity::Face, unique_hashes, counters) {
+                // A send only fails once the writer has stopped on an error, reported below
+                let _ = sender.send(polycube);
+            }
+            progress.inc();
+        });
+        // Closing the channel ends the writer's loop
+        drop(sender);
+        
+        writer.join().expect("stream writer thread panicked")
+    })?;
     
-    // Combine results
-    let polycubes: Vec<Polycube> = results;
+    progress.finish();
+    Ok(written)
+}
+
+// Generate all polycubes of size n so that an interrupted run can pick up where it left
+// off. Every finished size is saved to cubes_{k}.zst and loaded on restart; while a size
+// is being expanded, the shapes found and the base shapes already expanded are saved to
+// checkpoint_path after every `checkpoint_every` base shapes, and a restart skips those
+// base shapes. The checkpoint is removed once its size is cached.
+pub fn generate_polycubes_resumable(n: u8, checkpoint_path: &str, checkpoint_every: usize) -> Result<Vec<Polycube>, GeneratorError> {
+    if n <= 2 {
+        return Ok(generate_polycubes_with_policy(n, CachePolicy::None));
+    }
     
-    println!("\rGenerating polycubes n={}: 100%", n);
+    let cache_path = format!("cubes_{}.zst", n);
+    if Path::new(&cache_path).exists() {
+        println!("Loading polycubes n={} from cache", n);
+        return load_from_cache(&cache_path);
+    }
+    
+    let base_cubes = generate_polycubes_resumable(n - 1, checkpoint_path, checkpoint_every)?;
+    let polycubes = expand_layer_resumable(&base_cubes, n, checkpoint_path, checkpoint_every.max(1))?;
     println!("Found {} unique polycubes", polycubes.len());
     
-    // Cache results
-    if use_cache {
-        println!("Saving to cache...");
-        match save_to_cache(&polycubes, &cache_path) {
-            Ok(_) => println!("Saved to cache successfully"),
-            Err(e) => println!("Error saving to cache: {}", e)
+    save_to_cache(&polycubes, &cache_path)?;
+    match std::fs::remove_file(checkpoint_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(GeneratorError::Io(e)),
+        _ => {}
+    }
+    
+    Ok(polycubes)
+}
+
+// Progress of one expand_layer_resumable run, as saved to disk
+#[derive(Serialize, Deserialize)]
+struct GenerationCheckpoint {
+    n: u8,
+    base_count: usize,
+    // processed[i] is set once every child of base shape i is in found
+    processed: Vec<bool>,
+    found: Vec<Polycube>,
+}
+
+// expand_layer, resuming from and periodically saving a checkpoint
+fn expand_layer_resumable(base_cubes: &[Polycube], n: u8, checkpoint_path: &str, checkpoint_every: usize) -> Result<Vec<Polycube>, GeneratorError> {
+    // A checkpoint for another size or base layer is stale and ignored
+    let checkpoint = match load_checkpoint(checkpoint_path)? {
+        Some(checkpoint) if checkpoint.n == n && checkpoint.base_count == base_cubes.len() => {
+            let done = checkpoint.processed.iter().filter(|&&done| done).count();
+            println!("Resuming n={} from {}: {} of {} base polycubes already expanded",
+                n, checkpoint_path, done, base_cubes.len());
+            checkpoint
         }
+        _ => GenerationCheckpoint {
+            n,
+            base_count: base_cubes.len(),
+            processed: vec![false; base_cubes.len()],
+            found: Vec::new(),
+        },
+    };
+    
+    // Shapes claimed by base shapes that never finished are not in found, so those base
+    // shapes are expanded again and find them again
+    let unique_hashes: ShardedHashSet = checkpoint.found.iter().map(Polycube::get_canonical_hash).collect();
+    let pending: Vec<usize> = (0..base_cubes.len()).filter(|&i| !checkpoint.processed[i]).collect();
+    
+    let total = base_cubes.len();
+    let since_checkpoint = AtomicUsize::new(0);
+    let counters = StatsCounters::default();
+    let state = Mutex::new(checkpoint);
+    println!("Processing {} base polycubes of size {}", pending.len(), n - 1);
+    let progress = LayerProgress::new(n as usize, total - pending.len(), total);
+    
+    maybe_par_iter!(pending).for_each(|&index| {
+        let children = expand_base(&base_cubes[index], Connectivity::Face, &unique_hashes, &counters);
+        
+        // Record the children and the finished base together so a checkpoint is consistent
+        let mut state = state.lock().unwrap();
+        state.found.extend(children);
+        state.processed[index] = true;
+        
+        if since_checkpoint.fetch_add(1, Ordering::SeqCst) + 1 >= checkpoint_every {
+            since_checkpoint.store(0, Ordering::SeqCst);
+            if let Err(e) = save_checkpoint(checkpoint_path, &state) {
+                println!("\nError saving checkpoint: {}", e);
+            }
+        }
+        drop(state);
+        
+        progress.inc();
+    });
+    
+    progress.finish();
+    
+    Ok(state.into_inner().unwrap().found)
+}
+
+// Write a checkpoint to a temporary file and rename it over the old one, so an
+// interruption mid-write never leaves a damaged checkpoint behind
+fn save_checkpoint(path: &str, checkpoint: &GenerationCheckpoint) -> Result<(), GeneratorError> {
+    let temp_path = format!("{}.tmp", path);
+    
+    let mut encoder = create_cache_encoder(&temp_path)?;
+    bincode::serialize_into(&mut encoder, checkpoint)?;
+    encoder.finish().map_err(GeneratorError::Compression)?;
+    
+    std::fs::rename(&temp_path, path).map_err(GeneratorError::Io)
+}
+
+// Read a checkpoint, or None if there is none
+fn load_checkpoint(path: &str) -> Result<Option<GenerationCheckpoint>, GeneratorError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(GeneratorError::Io(e)),
+    };
+    
+    let decoder = zstd::Decoder::new(file).map_err(GeneratorError::Compression)?;
+    Ok(Some(bincode::deserialize_from(decoder)?))
+}
+
+// Expand a single base polycube, returning the children not yet seen in unique_hashes
+fn expand_base(
+    base_cube: &Polycube,
+    connectivity: Connectivity,
+    unique_hashes: &impl DedupSet,
+    counters: &StatsCounters,
+) -> Vec<Polycube> {
+    // Get expansion positions
+    let positions = base_cube.get_expansion_positions(connectivity);
+    let mut stats = GenerationStats::default();
+    
+    let local_polycubes = positions.into_iter()
+        .filter_map(|position| expand_candidate(base_cube, position, connectivity, unique_hashes, &mut stats))
+        .collect();
+    
+    counters.record(&stats);
+    local_polycubes
+}
+
+// Add the cube at position to base_cube, returning the normalized child if it is connected
+// and no rotation of it was in unique_hashes yet
+fn expand_candidate(
+    base_cube: &Polycube,
+    position: Pos,
+    connectivity: Connectivity,
+    unique_hashes: &impl DedupSet,
+    stats: &mut GenerationStats,
+) -> Option<Polycube> {
+    // Create expanded shape
+    let expanded_shape = base_cube.expand_unchecked(position);
+    stats.candidates += 1;
+    
+    // Skip if not connected
+    if !expanded_shape.is_connected(connectivity) {
+        stats.disconnected += 1;
+        return None;
     }
     
-    polycubes
+    // Normalize
+    let normalized = expanded_shape.normalize();
+    
+    // Get canonical hash for uniqueness check (much faster than string representation)
+    let canonical_hash = normalized.get_canonical_hash();
+    
+    // Try to add to global uniqueness set
+    if unique_hashes.insert(canonical_hash) {
+        Some(normalized)
+    } else {
+        stats.duplicates += 1;
+        None
+    }
 }
 
-// Save polycubes to compressed cache
-fn save_to_cache(polycubes: &[Polycube], path: &str) -> Result<(), std::io::Error> {
-    let serialized = bincode::serialize(polycubes)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+// Number of independently locked shards in a ShardedHashSet or DiskHashSet (a power of two)
+const DEDUP_SHARDS: usize = 64;
+
+// Shard a canonical hash belongs to, from its top bits (the best mixed bits of an Fx hash)
+fn dedup_shard(hash: u64) -> usize {
+    (hash >> (u64::BITS - DEDUP_SHARDS.trailing_zeros())) as usize
+}
+
+// A set of canonical hashes that threads can insert into concurrently
+trait DedupSet: Sync {
+    // Returns true if the hash was not in the set yet
+    fn insert(&self, hash: u64) -> bool;
+}
+
+// Set of canonical hashes split into independently locked shards, picked by dedup_shard,
+// so threads inserting different shapes rarely wait on each other
+struct ShardedHashSet {
+    shards: Vec<Mutex<FxHashSet<u64>>>,
+}
+
+impl DedupSet for ShardedHashSet {
+    fn insert(&self, hash: u64) -> bool {
+        self.shards[dedup_shard(hash)].lock().unwrap().insert(hash)
+    }
+}
+
+impl Default for ShardedHashSet {
+    fn default() -> Self {
+        ShardedHashSet { shards: (0..DEDUP_SHARDS).map(|_| Mutex::new(FxHashSet::default())).collect() }
+    }
+}
+
+impl FromIterator<u64> for ShardedHashSet {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let set = ShardedHashSet::default();
+        for hash in iter {
+            set.insert(hash);
+        }
+        set
+    }
+}
+
+// DedupBackend::OnDisk: an open-addressing table of little-endian u64 slots in a file,
+// split into DEDUP_SHARDS regions that each have their own file handle and lock. Within a
+// region, linear probing starts at the slot picked by the low bits of the hash. A zero slot
+// is empty, so a hash of zero is stored as one, like any other hash collision.
+struct DiskHashSet {
+    path: PathBuf,
+    shards: Vec<Mutex<File>>,
+    slots_per_shard: u64,
+    // First I/O error, reported by finish; inserts after an error keep every shape
+    error: Mutex<Option<io::Error>>,
+}
+
+impl DiskHashSet {
+    // Create the table file with room for at least max_entries hashes, at most half full
+    // if they spread evenly over the shards
+    fn create(path: &Path, max_entries: usize) -> io::Result<Self> {
+        let slots_per_shard = (2 * max_entries / DEDUP_SHARDS + 1).next_power_of_two() as u64;
+        
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(slots_per_shard * DEDUP_SHARDS as u64 * 8)?;
+        // Each shard opens the file again: try_clone handles would share one file offset,
+        // so a seek in one shard could move another shard's read or write
+        let shards = (0..DEDUP_SHARDS)
+            .map(|_| OpenOptions::new().read(true).write(true).open(path).map(Mutex::new))
+            .collect::<io::Result<_>>()?;
+        
+        Ok(DiskHashSet { path: path.to_path_buf(), shards, slots_per_shard, error: Mutex::new(None) })
+    }
+
+    fn try_insert(&self, hash: u64) -> io::Result<bool> {
+        let stored = hash.max(1);
+        let shard = dedup_shard(hash);
+        let region = shard as u64 * self.slots_per_shard;
+        let mut file = self.shards[shard].lock().unwrap();
+        
+        let mut slot = hash & (self.slots_per_shard - 1);
+        for _ in 0..self.slots_per_shard {
+            let offset = (region + slot) * 8;
+            let mut bytes = [0u8; 8];
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut bytes)?;
+            
+            match u64::from_le_bytes(bytes) {
+                0 => {
+                    file.seek(SeekFrom::Start(offset))?;
+                    file.write_all(&stored.to_le_bytes())?;
+                    return Ok(true);
+                }
+                existing if existing == stored => return Ok(false),
+                _ => slot = (slot + 1) & (self.slots_per_shard - 1),
+            }
+        }
+        
+        Err(io::Error::other(format!("dedup table {} is full", self.path.display())))
+    }
+
+    // Report the first I/O error, if any, and delete the table file
+    fn finish(self) -> io::Result<()> {
+        match self.error.lock().unwrap().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl DedupSet for DiskHashSet {
+    fn insert(&self, hash: u64) -> bool {
+        self.try_insert(hash).unwrap_or_else(|e| {
+            self.error.lock().unwrap().get_or_insert(e);
+            true
+        })
+    }
+}
+
+impl Drop for DiskHashSet {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// Create a cache file and the zstd stream that compresses into it
+fn create_cache_encoder(path: &str) -> Result<zstd::Encoder<'static, File>, GeneratorError> {
+    let file = File::create(path).map_err(GeneratorError::Io)?;
+    zstd::Encoder::new(file, 3).map_err(GeneratorError::Compression)
+}
+
+// Write the magic and format version that start every cache stream
+fn write_cache_header<W: Write>(writer: &mut W) -> Result<(), GeneratorError> {
+    writer.write_all(&CACHE_MAGIC)
+        .and_then(|_| writer.write_all(&[CACHE_VERSION]))
+        .map_err(GeneratorError::Compression)
+}
+
+// Write one polycube as a little-endian u32 length followed by its bincode encoding
+fn write_cache_record<W: Write>(writer: &mut W, polycube: &Polycube) -> Result<(), GeneratorError> {
+    let serialized = bincode::serialize(polycube)?;
+    
+    writer.write_all(&(serialized.len() as u32).to_le_bytes())
+        .and_then(|_| writer.write_all(&serialized))
+        .map_err(GeneratorError::Compression)
+}
+
+// Read one length-prefixed polycube, or None at a clean end of stream
+fn read_cache_record<R: Read>(reader: &mut R) -> Result<Option<Polycube>, GeneratorError> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(GeneratorError::Compression(e)),
+    }
     
+    let mut buffer = vec![0u8; u32::from_le_bytes(length) as usize];
+    reader.read_exact(&mut buffer).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => GeneratorError::Corrupt("truncated record".to_string()),
+        _ => GeneratorError::Compression(e),
+    })?;
+    
+    Ok(Some(bincode::deserialize(&buffer)?))
+}
+
+// Cache files start with this magic and a format version byte. Legacy caches have no
+// header: they begin with bincode's u64 element count, which can never equal the magic.
+const CACHE_MAGIC: [u8; 8] = *b"PCUBECCH";
+const CACHE_VERSION: u8 = 1;
+
+// Save polycubes to compressed cache
+fn save_to_cache(polycubes: &[Polycube], path: &str) -> Result<(), GeneratorError> {
     // Create a file with zstd encoder
-    let file = File::create(path)?;
-    let mut encoder = zstd::Encoder::new(file, 3)?;
+    let mut encoder = create_cache_encoder(path)?;
     
-    // Write the serialized data
-    encoder.write_all(&serialized)?;
+    // Write the header and one length-prefixed record per polycube
+    write_cache_header(&mut encoder)?;
+    for polycube in polycubes {
+        write_cache_record(&mut encoder, polycube)?;
+    }
     
     // Finish the compression
-    encoder.finish()?;
+    encoder.finish().map_err(GeneratorError::Compression)?;
     
     Ok(())
 }
 
 // Load polycubes from compressed cache
-fn load_from_cache(path: &str) -> Result<Vec<Polycube>, std::io::Error> {
-    let file = File::open(path)?;
-    let mut decoder = zstd::Decoder::new(file)?;
+pub fn load_from_cache(path: &str) -> Result<Vec<Polycube>, GeneratorError> {
+    load_cache_streaming(path)?.collect()
+}
+
+// Read a cache one polycube at a time, without decompressing it all into memory.
+// Accepts both the current length-prefixed format and legacy whole-Vec caches.
+pub fn load_cache_streaming(path: &str) -> Result<impl Iterator<Item = Result<Polycube, GeneratorError>>, GeneratorError> {
+    let file = File::open(path).map_err(GeneratorError::Io)?;
+    let mut decoder = zstd::Decoder::new(file).map_err(GeneratorError::Compression)?;
+    
+    let read_header = |e: io::Error| match e.kind() {
+        io::ErrorKind::UnexpectedEof => GeneratorError::Corrupt("missing header".to_string()),
+        _ => GeneratorError::Compression(e),
+    };
+    
+    let mut header = [0u8; 8];
+    decoder.read_exact(&mut header).map_err(read_header)?;
+    
+    let legacy_remaining = if header == CACHE_MAGIC {
+        let mut version = [0u8; 1];
+        decoder.read_exact(&mut version).map_err(read_header)?;
+        if version[0] != CACHE_VERSION {
+            return Err(GeneratorError::Corrupt(format!("unsupported cache version {}", version[0])));
+        }
+        None
+    } else {
+        // Legacy format: the header is the bincode length of the serialized Vec
+        Some(u64::from_le_bytes(header))
+    };
+    
+    Ok(CacheRecords { reader: decoder, legacy_remaining, done: false })
+}
+
+// Iterator over the polycubes stored in a cache stream
+struct CacheRecords<R> {
+    reader: R,
+    legacy_remaining: Option<u64>,
+    done: bool,
+}
+
+impl<R: Read> Iterator for CacheRecords<R> {
+    type Item = Result<Polycube, GeneratorError>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        
+        let record = match &mut self.legacy_remaining {
+            Some(0) => Ok(None),
+            Some(remaining) => {
+                *remaining -= 1;
+                bincode::deserialize_from(&mut self.reader)
+                    .map(Some)
+                    .map_err(GeneratorError::Serialize)
+            }
+            None => read_cache_record(&mut self.reader),
+        };
+        
+        match record {
+            Ok(Some(polycube)) => Some(Ok(polycube)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+// Known counts for validation. Three different enumerations appear in the literature:
+//   fixed     (OEIS A001931) - distinct up to translation only
+//   one-sided (OEIS A000162) - distinct up to rotation; this is what generate_polycubes produces
+//   free      (OEIS A038119) - distinct up to rotation and reflection
+
+// Integer type for polycube counts. Fixed counts grow about 8x per size, so u128 leaves
+// room well beyond the tabulated n=18 and for fixed counts 24 times the one-sided ones.
+pub type Count = u128;
+
+// Every count for one size, from a single generation pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    pub n: u8,
+    // Distinct up to translation (OEIS A001931)
+    pub fixed: Count,
+    // Distinct up to rotation (OEIS A000162)
+    pub one_sided: Count,
+    // Distinct up to rotation and reflection (OEIS A038119)
+    pub free: Count,
+    // One-sided shapes that are rotations of their own mirror image
+    pub achiral: Count,
+    // Number of one-sided shapes with each symmetry order (rotations fixing the shape)
+    pub symmetry_orders: BTreeMap<usize, Count>,
+}
+
+impl Analysis {
+    // Check the relations between the counts: each one-sided shape has 24 / order fixed
+    // orientations, and free shapes pair up chiral mirror images
+    pub fn is_consistent(&self) -> bool {
+        let fixed: Count = self.symmetry_orders.iter().map(|(&order, &count)| count * (24 / order) as Count).sum();
+        let one_sided: Count = self.symmetry_orders.values().sum();
+        
+        fixed == self.fixed && one_sided == self.one_sided && 2 * self.free == self.one_sided + self.achiral
+    }
+
+    pub fn print(&self) {
+        println!("\nAnalysis of n={}:", self.n);
+        println!("  Fixed: {}", self.fixed);
+        println!("  One-sided: {}", self.one_sided);
+        println!("  Free: {}", self.free);
+        println!("  Achiral: {}", self.achiral);
+        println!("  Shapes by symmetry order:");
+        for (order, count) in &self.symmetry_orders {
+            println!("    {:>2}: {}", order, count);
+        }
+    }
+}
+
+// Generate the one-sided polycubes of size n once (using the cache) and derive the fixed
+// and free counts from each shape's symmetry order and chirality
+pub fn analyze(n: u8) -> Analysis {
+    let polycubes = generate_polycubes_with_policy(n, CachePolicy::default());
+    analyze_shapes(n, &polycubes)
+}
+
+// Analysis of an already generated set of one-sided polycubes of size n
+pub(crate) fn analyze_shapes(n: u8, polycubes: &[Polycube]) -> Analysis {
+    let shapes: Vec<(usize, bool)> = maybe_par_iter!(polycubes)
+        .map(|polycube| {
+            let achiral = polycube.mirror_all().canonical_form() == polycube.canonical_form();
+            (polycube.symmetry_order(), achiral)
+        })
+        .collect();
+    
+    let mut symmetry_orders = BTreeMap::new();
+    for &(order, _) in &shapes {
+        *symmetry_orders.entry(order).or_insert(0) += 1;
+    }
     
-    let mut decompressed = Vec::new();
-    decoder.read_to_end(&mut decompressed)?;
+    let one_sided = shapes.len() as Count;
+    let achiral = shapes.iter().filter(|&&(_, achiral)| achiral).count() as Count;
     
-    bincode::deserialize(&decompressed)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    Analysis {
+        n,
+        fixed: shapes.iter().map(|&(order, _)| (24 / order) as Count).sum(),
+        one_sided,
+        free: (one_sided + achiral) / 2,
+        achiral,
+        symmetry_orders,
+    }
+}
+
+// Number of fixed polycubes of size n (OEIS A001931)
+pub fn get_known_fixed_count(n: u8) -> Option<Count> {
+    match n {
+        1 => Some(1),
+        2 => Some(3),
+        3 => Some(15),
+        4 => Some(86),
+        5 => Some(534),
+        6 => Some(3481),
+        7 => Some(23502),
+        8 => Some(162913),
+        9 => Some(1152870),
+        10 => Some(8294738),
+        11 => Some(60494549),
+        12 => Some(446205905),
+        13 => Some(3322769321),
+        14 => Some(24946773111),
+        15 => Some(188625900446),
+        16 => Some(1435074454755),
+        17 => Some(10977812452428),
+        18 => Some(84384157287999),
+        _ => None,
+    }
 }
 
-// Known counts for validation
-pub fn get_known_count(n: u8) -> Option<u64> {
+// Number of one-sided polycubes of size n, mirror images counted separately (OEIS A000162)
+pub fn get_known_one_sided_count(n: u8) -> Option<Count> {
     match n {
         1 => Some(1),
         2 => Some(1),
@@ -146,16 +1172,144 @@ pub fn get_known_count(n: u8) -> Option<u64> {
         10 => Some(346543),
         11 => Some(2522522),
         12 => Some(18598427),
-        13 => Some(139333147),
-        14 => Some(1056657611),
-        15 => Some(8107839447),
-        16 => Some(62709211271),
-        17 => Some(489997729602),
-        18 => Some(3847265309118),
+        13 => Some(138462649),
+        14 => Some(1039496297),
+        15 => Some(7859514470),
+        16 => Some(59795121480),
+        17 => Some(457409613979),
+        18 => Some(3516009200564),
         _ => None,
     }
 }
 
+// Number of free polycubes of size n, mirror images identified (OEIS A038119)
+pub fn get_known_free_count(n: u8) -> Option<Count> {
+    match n {
+        1 => Some(1),
+        2 => Some(1),
+        3 => Some(2),
+        4 => Some(7),
+        5 => Some(23),
+        6 => Some(112),
+        7 => Some(607),
+        8 => Some(3811),
+        9 => Some(25413),
+        10 => Some(178083),
+        11 => Some(1279537),
+        12 => Some(9371094),
+        13 => Some(69513546),
+        14 => Some(520878101),
+        15 => Some(3934285874),
+        16 => Some(29915913663),
+        17 => Some(228779436642),
+        18 => Some(1758309900713),
+        _ => None,
+    }
+}
+
+// First problem self_check found in a generated set; indices are into that set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelfCheckError {
+    WrongSize { index: usize, found: usize, expected: usize },
+    Disconnected { index: usize },
+    // Some coordinate minimum is not 0
+    NotNormalized { index: usize },
+    // Two shapes are rotations of each other
+    Duplicate { first: usize, second: usize },
+}
+
+impl fmt::Display for SelfCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelfCheckError::WrongSize { index, found, expected } =>
+                write!(f, "shape {} has {} cubes, expected {}", index, found, expected),
+            SelfCheckError::Disconnected { index } => write!(f, "shape {} is not face-connected", index),
+            SelfCheckError::NotNormalized { index } => write!(f, "shape {} is not normalized", index),
+            SelfCheckError::Duplicate { first, second } =>
+                write!(f, "shapes {} and {} are rotations of each other", first, second),
+        }
+    }
+}
+
+impl std::error::Error for SelfCheckError {}
+
+// Check the invariants of a generated size-n set in one pass: every shape has n cubes, is
+// face-connected and normalized, and no two shares a canonical form. Returns the first
+// violation, checking shapes in order.
+pub fn self_check(polycubes: &[Polycube], n: u8) -> Result<(), SelfCheckError> {
+    let expected = n as usize;
+    let mut seen: FxHashMap<CanonicalKey, usize> = FxHashMap::default();
+    
+    for (index, polycube) in polycubes.iter().enumerate() {
+        if polycube.cubes.len() != expected {
+            return Err(SelfCheckError::WrongSize { index, found: polycube.cubes.len(), expected });
+        }
+        if !polycube.is_connected(Connectivity::Face) {
+            return Err(SelfCheckError::Disconnected { index });
+        }
+        
+        if polycube.bounding_box().is_some_and(|(min, _)| min != Pos::new(0, 0, 0)) {
+            return Err(SelfCheckError::NotNormalized { index });
+        }
+        
+        let key = polycube.canonical_key();
+        if let Some(&first) = seen.get(&key) {
+            return Err(SelfCheckError::Duplicate { first, second: index });
+        }
+        seen.insert(key, index);
+    }
+    
+    Ok(())
+}
+
+// Outcome of comparing a computed count against a known value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationReport {
+    pub found: Count,
+    pub expected: Count,
+    // found - expected: negative when shapes are missing, positive when there are extras
+    pub delta: i128,
+}
+
+impl VerificationReport {
+    pub fn new(found: Count, expected: Count) -> Self {
+        VerificationReport {
+            found,
+            expected,
+            delta: found as i128 - expected as i128,
+        }
+    }
+
+    pub fn matches(&self) -> bool {
+        self.delta == 0
+    }
+
+    // Print the comparison in the style used throughout the CLI
+    pub fn print(&self, n: u8) {
+        println!("Expected count for n={}: {}", n, self.expected);
+        println!("Found count: {}", self.found);
+
+        if self.delta < 0 {
+            println!("WARNING: Missing {} polycubes!", -self.delta);
+        } else if self.delta > 0 {
+            println!("WARNING: Found {} extra polycubes!", self.delta);
+        } else {
+            println!("Count matches expected value.");
+        }
+    }
+}
+
+// Compare a count of generated (one-sided) polycubes against the known value, if tabulated
+pub fn verify_one_sided_count(n: u8, found: Count) -> Option<VerificationReport> {
+    get_known_one_sided_count(n).map(|expected| VerificationReport::new(found, expected))
+}
+
+// Known counts for the shapes generate_polycubes produces (one-sided polycubes)
+#[deprecated(note = "ambiguous name; use get_known_one_sided_count, get_known_free_count or get_known_fixed_count")]
+pub fn get_known_count(n: u8) -> Option<Count> {
+    get_known_one_sided_count(n)
+}
+
 // Generate summary statistics
 pub fn generate_summary(polycubes: &[Polycube]) {
     if polycubes.is_empty() {
@@ -210,4 +1364,436 @@ pub fn generate_summary(polycubes: &[Polycube]) {
             println!("    Max dim {}: {} shapes", dim, count);
         }
     }
-}
\ No newline at end of file
+    
+    println!("  Groups by bounding box:");
+    for (dims, members) in group_by_bbox(polycubes) {
+        println!("    {}×{}×{}: {} shapes", dims.0, dims.1, dims.2, members.len());
+    }
+    
+    println!("  Distribution by branch points:");
+    for (branches, count) in branch_point_tally(polycubes) {
+        println!("    {} branch points: {} shapes", branches, count);
+    }
+    
+    println!("\n  Largest bounding boxes:");
+    for polycube in largest_bounding_boxes(polycubes, SUMMARY_TOP_K) {
+        let (x, y, z) = polycube.normalize().get_dimensions();
+        println!("    {}×{}×{} (volume {})", x, y, z, x as usize * y as usize * z as usize);
+    }
+    
+    println!("  Most elongated:");
+    for polycube in most_elongated(polycubes, SUMMARY_TOP_K) {
+        let (x, y, z) = polycube.normalize().get_dimensions();
+        let (longest, shortest) = (x.max(y).max(z), x.min(y).min(z));
+        println!("    {}×{}×{} (aspect {:.2})", x, y, z, longest as f64 / shortest as f64);
+    }
+}
+
+// Group shapes into families by bounding box, keyed by the box dimensions sorted
+// smallest first so every orientation of a box shares a key. Each group lists indices
+// into `polycubes`, in input order.
+pub fn group_by_bbox(polycubes: &[Polycube]) -> BTreeMap<(i8, i8, i8), Vec<usize>> {
+    let mut groups: BTreeMap<(i8, i8, i8), Vec<usize>> = BTreeMap::new();
+    for (index, polycube) in polycubes.iter().enumerate() {
+        let (x, y, z) = polycube.normalize().get_dimensions();
+        let mut dims = [x, y, z];
+        dims.sort_unstable();
+        groups.entry((dims[0], dims[1], dims[2])).or_default().push(index);
+    }
+    groups
+}
+
+// Number of shapes with each branch point count (Polycube::branch_point_count)
+pub fn branch_point_tally(polycubes: &[Polycube]) -> BTreeMap<usize, usize> {
+    let mut tally = BTreeMap::new();
+    for polycube in polycubes {
+        *tally.entry(polycube.branch_point_count()).or_insert(0) += 1;
+    }
+    tally
+}
+
+// Number of shapes listed in each top-k section of generate_summary
+pub const SUMMARY_TOP_K: usize = 5;
+
+// The k shapes with the largest bounding box volume, largest first; ties keep input order
+pub fn largest_bounding_boxes(polycubes: &[Polycube], k: usize) -> Vec<&Polycube> {
+    let mut ranked: Vec<(usize, &Polycube)> = polycubes.iter()
+        .map(|polycube| {
+            let (x, y, z) = polycube.normalize().get_dimensions();
+            (x as usize * y as usize * z as usize, polycube)
+        })
+        .collect();
+    ranked.sort_by_key(|&(volume, _)| std::cmp::Reverse(volume));
+    
+    ranked.into_iter().take(k).map(|(_, polycube)| polycube).collect()
+}
+
+// The k shapes with the largest aspect ratio (longest over shortest bounding box side),
+// most elongated first; ties keep input order
+pub fn most_elongated(polycubes: &[Polycube], k: usize) -> Vec<&Polycube> {
+    let mut ranked: Vec<((usize, usize), &Polycube)> = polycubes.iter()
+        .map(|polycube| {
+            let (x, y, z) = polycube.normalize().get_dimensions();
+            ((x.max(y).max(z) as usize, x.min(y).min(z) as usize), polycube)
+        })
+        .collect();
+    // Compare longest_a / shortest_a with longest_b / shortest_b by cross-multiplying
+    ranked.sort_by(|((long_a, short_a), _), ((long_b, short_b), _)| (long_b * short_a).cmp(&(long_a * short_b)));
+    
+    ranked.into_iter().take(k).map(|(_, polycube)| polycube).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("polycube_{}_{}.zst", name, std::process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn canonical_forms(polycubes: &[Polycube]) -> FxHashSet<Vec<Pos>> {
+        polycubes.iter().map(Polycube::canonical_form).collect()
+    }
+
+    // Prefixes of the OEIS sequences, n = 1..=18
+    const A001931: [Count; 18] = [
+        1, 3, 15, 86, 534, 3481, 23502, 162913, 1152870, 8294738, 60494549, 446205905, 3322769321,
+        24946773111, 188625900446, 1435074454755, 10977812452428, 84384157287999,
+    ];
+    const A000162: [Count; 18] = [
+        1, 1, 2, 8, 29, 166, 1023, 6922, 48311, 346543, 2522522, 18598427, 138462649, 1039496297,
+        7859514470, 59795121480, 457409613979, 3516009200564,
+    ];
+    const A038119: [Count; 18] = [
+        1, 1, 2, 7, 23, 112, 607, 3811, 25413, 178083, 1279537, 9371094, 69513546, 520878101,
+        3934285874, 29915913663, 228779436642, 1758309900713,
+    ];
+
+    #[test]
+    fn known_tables_match_oeis() {
+        let tables = [get_known_fixed_count, get_known_one_sided_count, get_known_free_count];
+        for (table, oeis) in tables.into_iter().zip([A001931, A000162, A038119]) {
+            let prefix: Vec<Count> = (1..=18).map(|n| table(n).unwrap()).collect();
+            assert_eq!(prefix, oeis);
+            assert_eq!((table(0), table(19)), (None, None));
+        }
+
+        // A shape has at most 24 fixed orientations, and a free shape is two one-sided
+        // shapes unless it is achiral
+        for n in 1..=18u8 {
+            let (fixed, one_sided, free) = (A001931[n as usize - 1], A000162[n as usize - 1], A038119[n as usize - 1]);
+            assert!(free <= one_sided && one_sided <= 2 * free && fixed <= 24 * one_sided, "n={}", n);
+        }
+        // 2 * free - one-sided is the number of achiral polycubes, here for n = 13..=18
+        let achiral: Vec<Count> = (12..18).map(|i| 2 * A038119[i] - A000162[i]).collect();
+        assert_eq!(achiral, [564443, 2259905, 9057278, 36705846, 149259305, 610600862]);
+    }
+
+    // A streamed cache reloads into the same shapes generate_polycubes returns
+    #[test]
+    fn streamed_cache_reloads_as_generated() {
+        let path = temp_path("streamed_cache");
+        let written = generate_to_cache(6, &path, CachePolicy::None).unwrap();
+        let reloaded = load_from_cache(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let generated = generate_polycubes(6, false);
+        assert_eq!(written, generated.len() as u64);
+        assert_eq!(reloaded.len(), generated.len());
+        assert_eq!(canonical_forms(&reloaded), canonical_forms(&generated));
+    }
+
+    // Reading a cache shape by shape gives the shapes a bulk read gives, in the same order,
+    // for both the length-prefixed format and a legacy whole-Vec cache
+    #[test]
+    fn streaming_read_equals_bulk_read() {
+        let polycubes = generate_polycubes(5, false);
+
+        let path = temp_path("streaming_read");
+        save_to_cache(&polycubes, &path).unwrap();
+        let streamed: Vec<Polycube> = load_cache_streaming(&path).unwrap().map(Result::unwrap).collect();
+        let bulk = load_from_cache(&path).unwrap();
+        assert_eq!(streamed, polycubes);
+        assert_eq!(bulk, polycubes);
+
+        let legacy = zstd::encode_all(bincode::serialize(&polycubes).unwrap().as_slice(), 3).unwrap();
+        std::fs::write(&path, legacy).unwrap();
+        let streamed: Vec<Polycube> = load_cache_streaming(&path).unwrap().map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(streamed, polycubes);
+    }
+
+    // 12 = 1·1·12 = 1·2·6 = 1·3·4 = 2·2·3, and a prime size only has its straight line
+    #[test]
+    fn box_fillers_are_the_factorizations() {
+        let fillers = generate_box_fillers(12);
+        assert_eq!(fillers.len(), 4);
+        assert!(fillers.iter().all(|polycube| polycube.cubes.len() == 12 && polycube.fills_bounding_box()));
+        assert_eq!(canonical_forms(&fillers).len(), 4);
+
+        let filtered = generate_polycubes_single_threaded(6).into_iter().filter(Polycube::fills_bounding_box).collect::<Vec<_>>();
+        assert_eq!(canonical_forms(&generate_box_fillers(6)), canonical_forms(&filtered));
+        assert_eq!(generate_box_fillers(7).len(), 1);
+    }
+
+    // Every shape found at each level from 3 up is a candidate that was neither
+    // disconnected nor a duplicate
+    #[test]
+    fn stats_candidates_cover_the_output() {
+        let (polycubes, stats) = generate_polycubes_with_stats(6, CachePolicy::None);
+        assert_eq!(polycubes.len(), 166);
+        assert!(stats.candidates >= polycubes.len());
+        assert!(stats.duplicates > 0);
+
+        let found: Count = (3..=6).map(|n| get_known_one_sided_count(n).unwrap()).sum();
+        assert_eq!((stats.candidates - stats.disconnected - stats.duplicates) as Count, found);
+    }
+
+    // A missing file, data that is not zstd, and zstd data without a valid header each
+    // surface as their own variant
+    #[test]
+    fn cache_errors_name_their_cause() {
+        let path = temp_path("cache_errors");
+        assert!(matches!(load_from_cache(&path), Err(GeneratorError::Io(e)) if e.kind() == io::ErrorKind::NotFound));
+
+        std::fs::write(&path, b"not a zstd stream").unwrap();
+        assert!(matches!(load_from_cache(&path), Err(GeneratorError::Compression(_))));
+
+        std::fs::write(&path, zstd::encode_all(&b""[..], 0).unwrap()).unwrap();
+        assert!(matches!(load_from_cache(&path), Err(GeneratorError::Corrupt(message)) if message == "missing header"));
+
+        let mut header = CACHE_MAGIC.to_vec();
+        header.push(CACHE_VERSION + 1);
+        std::fs::write(&path, zstd::encode_all(&header[..], 0).unwrap()).unwrap();
+        let result = load_from_cache(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(GeneratorError::Corrupt(message)) if message.starts_with("unsupported cache version")));
+    }
+
+    // Leaves the checkpoint a run of size n killed after expanding the first `done` base
+    // shapes would have saved
+    fn interrupted_checkpoint(base_cubes: &[Polycube], n: u8, done: usize) -> GenerationCheckpoint {
+        let unique_hashes = ShardedHashSet::default();
+        let found = base_cubes[..done].iter()
+            .flat_map(|base| expand_base(base, Connectivity::Face, &unique_hashes, &StatsCounters::default()))
+            .collect();
+        let mut processed = vec![false; base_cubes.len()];
+        processed[..done].fill(true);
+        GenerationCheckpoint { n, base_count: base_cubes.len(), processed, found }
+    }
+
+    // Resuming a layer from a checkpoint finds exactly the shapes of an uninterrupted run
+    #[test]
+    fn resumed_layer_matches_a_full_run() {
+        let path = temp_path("resume_checkpoint");
+        let base_cubes = generate_polycubes_single_threaded(5);
+        let full = canonical_forms(&generate_polycubes_single_threaded(6));
+
+        for done in [0, 1, 13, base_cubes.len()] {
+            save_checkpoint(&path, &interrupted_checkpoint(&base_cubes, 6, done)).unwrap();
+            let resumed = expand_layer_resumable(&base_cubes, 6, &path, 5).unwrap();
+            assert_eq!(resumed.len(), 166, "resumed after {} base shapes", done);
+            assert_eq!(canonical_forms(&resumed), full);
+        }
+
+        // A checkpoint of another size is ignored
+        save_checkpoint(&path, &interrupted_checkpoint(&base_cubes, 7, 13)).unwrap();
+        let resumed = expand_layer_resumable(&base_cubes, 6, &path, 5).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(canonical_forms(&resumed), full);
+    }
+
+    // The global pool can be sized only once; asking again keeps it and reports its real
+    // size. With one thread, generation and counting still give the known counts.
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn thread_pool_is_configured_once() {
+        let threads = configure_thread_pool(1);
+        assert_eq!(threads, rayon::current_num_threads());
+        assert_eq!(configure_thread_pool(threads + 2), threads);
+
+        assert_eq!(generate_polycubes_with_policy(6, CachePolicy::None).len(), 166);
+        let config = crate::safe_counter::CounterConfig { threads: 1, show_progress: false, force_compute: true, ..Default::default() };
+        assert_eq!(crate::safe_counter::count_fixed_polycubes(6, Some(config)), get_known_fixed_count(6).unwrap());
+    }
+
+    #[test]
+    fn analysis_counts_are_related() {
+        for n in 1..=6u8 {
+            let analysis = analyze_shapes(n, &generate_polycubes_single_threaded(n));
+            assert!(analysis.is_consistent(), "n={}", n);
+            assert_eq!(analysis.free * 2, analysis.one_sided + analysis.achiral);
+            assert_eq!(
+                (Some(analysis.fixed), Some(analysis.one_sided), Some(analysis.free)),
+                (get_known_fixed_count(n), get_known_one_sided_count(n), get_known_free_count(n)),
+                "n={}", n);
+        }
+
+        // Of the eight one-sided tetracubes, all but the chiral pair are achiral
+        let analysis = analyze_shapes(4, &generate_polycubes_single_threaded(4));
+        assert_eq!(analysis.achiral, 6);
+    }
+
+    // Flat pentacubes are the 12 pentominoes, filtered at the end or pruned on the way
+    #[test]
+    fn flat_filter_gives_the_pentominoes() {
+        let flat = generate_filtered(5, Polycube::is_flat);
+        assert_eq!(flat.len(), 12);
+        let expected: Vec<Polycube> = generate_polycubes_single_threaded(5).into_iter().filter(Polycube::is_flat).collect();
+        assert_eq!(canonical_forms(&flat), canonical_forms(&expected));
+
+        let pruned = generate_filtered_pruned(5, Polycube::is_flat, Polycube::is_flat);
+        assert_eq!(canonical_forms(&pruned), canonical_forms(&expected));
+        assert!(generate_filtered(5, |_| false).is_empty());
+    }
+
+    // Of the eight tetracubes only the T and the tripod have a branch point
+    #[test]
+    fn branch_point_tally_covers_every_shape() {
+        let tally = branch_point_tally(&generate_polycubes_single_threaded(4));
+        assert_eq!(tally, BTreeMap::from([(0, 6), (1, 2)]));
+        let pentacubes = generate_polycubes_single_threaded(5);
+        assert_eq!(branch_point_tally(&pentacubes).values().sum::<usize>(), pentacubes.len());
+    }
+
+    // Excluding every pentacube leaves nothing; excluding half leaves the other half
+    #[test]
+    fn excluding_known_shapes_returns_the_rest() {
+        let pentacubes = generate_polycubes_single_threaded(5);
+        let keys: Vec<CanonicalKey> = pentacubes.iter().map(Polycube::canonical_key).collect();
+
+        let all: FxHashSet<CanonicalKey> = keys.iter().cloned().collect();
+        assert!(generate_polycubes_excluding(5, &all).is_empty());
+
+        let (first, second) = keys.split_at(keys.len() / 2);
+        let rest: FxHashSet<CanonicalKey> = generate_polycubes_excluding(5, &first.iter().cloned().collect())
+            .iter()
+            .map(Polycube::canonical_key)
+            .collect();
+        assert_eq!(rest, second.iter().cloned().collect());
+
+        assert_eq!(generate_polycubes_excluding(5, &FxHashSet::default()).len(), 29);
+    }
+
+    // The flat groups are the pentominoes by box: I; P, U; L, N, Y; and F, T, V, W, X, Z
+    #[test]
+    fn pentacube_bbox_groups() {
+        let pentacubes = generate_polycubes_single_threaded(5);
+        let groups = group_by_bbox(&pentacubes);
+        let sizes: Vec<((i8, i8, i8), usize)> = groups.iter().map(|(&dims, indices)| (dims, indices.len())).collect();
+        assert_eq!(sizes, [((1, 1, 5), 1), ((1, 2, 3), 2), ((1, 2, 4), 3), ((1, 3, 3), 6), ((2, 2, 2), 2), ((2, 2, 3), 15)]);
+
+        let mut indices: Vec<usize> = groups.into_values().flatten().collect();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..pentacubes.len()).collect::<Vec<_>>());
+    }
+
+    // Each kind of corruption is reported at the first shape that shows it
+    #[test]
+    fn self_check_finds_corrupted_shapes() {
+        let good = generate_polycubes_single_threaded(5);
+        assert_eq!(self_check(&good, 5), Ok(()));
+
+        let mut corrupted = good.clone();
+        corrupted[3] = corrupted[3].without(corrupted[3].cubes[0]);
+        assert_eq!(self_check(&corrupted, 5), Err(SelfCheckError::WrongSize { index: 3, found: 4, expected: 5 }));
+
+        let mut corrupted = good.clone();
+        corrupted[4] = Polycube::new(vec![Pos::new(0, 0, 0), Pos::new(1, 0, 0), Pos::new(3, 0, 0), Pos::new(4, 0, 0), Pos::new(5, 0, 0)]);
+        assert_eq!(self_check(&corrupted, 5), Err(SelfCheckError::Disconnected { index: 4 }));
+
+        let mut corrupted = good.clone();
+        corrupted[5] = corrupted[5].translate(Pos::new(0, 1, 0));
+        assert_eq!(self_check(&corrupted, 5), Err(SelfCheckError::NotNormalized { index: 5 }));
+
+        let mut corrupted = good;
+        corrupted.push(corrupted[2].rotate(17));
+        assert_eq!(self_check(&corrupted, 5), Err(SelfCheckError::Duplicate { first: 2, second: 29 }));
+    }
+
+    // Threads racing to insert the same hashes: each hash is new to exactly one of them
+    #[test]
+    fn sharded_set_admits_each_hash_once() {
+        let set = ShardedHashSet::default();
+        let admitted = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let new = (0..5000u64).filter(|&i| set.insert(i.wrapping_mul(0x9E37_79B9_7F4A_7C15))).count();
+                    admitted.fetch_add(new, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(admitted.load(Ordering::SeqCst), 5000);
+        assert!(set.shards.iter().all(|shard| !shard.lock().unwrap().is_empty()));
+    }
+
+    // One task per (base, position) pair, and running them on several threads finds each
+    // shape once
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn per_position_tasks_find_each_shape_once() {
+        let base_cubes = generate_polycubes_single_threaded(5);
+        let tasks = expansion_tasks(&base_cubes, Connectivity::Face);
+        let positions: usize = base_cubes.iter().map(|base| base.get_expansion_positions(Connectivity::Face).len()).sum();
+        assert_eq!(tasks.len(), positions);
+
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        let children = pool.install(|| {
+            expand_tasks(&base_cubes, &tasks, Connectivity::Face, &ShardedHashSet::default(), &StatsCounters::default())
+        });
+        assert_eq!(children.len(), 166);
+        assert_eq!(canonical_forms(&children), canonical_forms(&generate_polycubes_single_threaded(6)));
+    }
+
+    // A threshold of one stops long before the 1023 heptacubes have all been checked;
+    // thresholds at and past the true count give true and false
+    #[test]
+    fn count_at_least_stops_at_the_threshold() {
+        let checked = AtomicUsize::new(0);
+        assert!(count_at_least(7, 1, |_| {
+            checked.fetch_add(1, Ordering::Relaxed);
+            true
+        }));
+        assert!(checked.load(Ordering::Relaxed) < 1023);
+
+        assert!(count_at_least(5, 29, |_| true));
+        assert!(!count_at_least(5, 30, |_| true));
+        assert!(count_at_least(5, 12, Polycube::is_flat));
+        assert!(!count_at_least(5, 13, Polycube::is_flat));
+        assert!(!count_at_least(4, usize::MAX, |_| true));
+    }
+
+    // Every tetracube found for an L-shaped shadow casts exactly that shadow from above,
+    // together they cover it, and they are the tetracubes that cast it in some orientation
+    #[test]
+    fn projection_matches_an_l_silhouette() {
+        let silhouette = [(0, 0), (1, 0), (0, 1)];
+        let target: FxHashSet<(i8, i8)> = silhouette.into_iter().collect();
+        let shadow = |polycube: &Polycube| {
+            normalized_cells(polycube.cubes.iter().map(|&pos| Axis::Z.project(pos))).unwrap()
+        };
+
+        let found = generate_with_projection(4, Axis::Z, &silhouette);
+        assert!(!found.is_empty());
+        let mut covered = FxHashSet::default();
+        for polycube in &found {
+            assert_eq!(polycube.cubes.len(), 4);
+            assert_eq!(shadow(polycube), target);
+            covered.extend(shadow(polycube));
+        }
+        assert_eq!(covered, target);
+
+        let expected: FxHashSet<Vec<Pos>> = generate_polycubes_single_threaded(4).into_iter()
+            .filter(|polycube| all_rotations(polycube).iter().any(|rotated| shadow(rotated) == target))
+            .map(|polycube| polycube.canonical_form())
+            .collect();
+        assert_eq!(canonical_forms(&found), expected);
+        assert_eq!(found.len(), expected.len());
+
+        assert!(generate_with_projection(4, Axis::Z, &[]).is_empty());
+        assert!(generate_with_projection(2, Axis::Z, &silhouette).is_empty());
+    }
+}
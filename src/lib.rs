@@ -1,10 +1,41 @@
+// `.par_iter()` with the `parallel` feature and `.iter()` without it, so the same
+// iterator chain runs on rayon or on the calling thread. Callers import rayon::prelude::*
+// under the same feature.
+macro_rules! maybe_par_iter {
+    ($items:expr) => {{
+        #[cfg(feature = "parallel")]
+        let iter = $items.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let iter = $items.iter();
+        iter
+    }};
+}
+
 pub mod polycube;
+pub mod polycube_set;
 pub mod rotation;
 pub mod generator;
 pub mod polycube_exporter;
 pub mod safe_counter;
+pub mod benchmark;
+pub mod api;
+pub mod naming;
+pub mod tiling;
+mod progress;
+#[cfg(feature = "async")]
+pub mod async_counter;
 
 // Re-export common items for easier use
-pub use polycube::{Polycube, Pos};
-pub use generator::{generate_polycubes, get_known_count};
-pub use safe_counter::count_polycubes;
\ No newline at end of file
+pub use polycube::{Axis, Connectivity, Direction, Polycube, Pos, ShapeSignature};
+pub use polycube_set::{PolycubeIndex, PolycubeSet, PolycubeView};
+pub use rotation::CanonicalKey;
+#[allow(deprecated)]
+pub use generator::get_known_count;
+pub use generator::{
+    analyze, count_at_least, generate_filtered, generate_filtered_pruned, generate_from_cached_base, generate_polycubes, generate_polycubes_excluding,
+    generate_polycubes_single_threaded, generate_polycubes_with_config, generate_polycubes_with_connectivity,
+    generate_polycubes_with_policy, generate_polycubes_with_stats, generate_streaming, generate_with_projection,
+    get_known_fixed_count, get_known_free_count, get_known_one_sided_count, self_check, CachePolicy, Count, DedupBackend,
+    GenerationStats, GeneratorConfig, GeneratorError, SelfCheckError,
+};
+pub use safe_counter::{count_polycubes, count_polycubes_single_threaded};
\ No newline at end of file
@@ -2,50 +2,150 @@ use std::env;
 use std::io::{self, Write};
 use std::time::Instant;
 
-mod polycube;
-mod rotation;
-mod generator;
-mod polycube_exporter;
-mod safe_counter;
+use polycube_generator::generator::{
+    configure_thread_pool, generate_polycubes_with_policy, verify_one_sided_count, generate_summary, CachePolicy, Count,
+};
+use polycube_generator::polycube_exporter::{self, SortKey};
+use polycube_generator::safe_counter::{
+    count_polycubes_with_report, export_bfile, verify_against, CounterConfig, SymmetryMode,
+};
 
-use generator::{generate_polycubes, get_known_count, generate_summary};
-use safe_counter::count_polycubes;
+mod cli;
 
 fn main() -> io::Result<()> {
-    println!("Polycube Generator and Counter (Rust)");
-    println!("=====================================");
-    
     // Parse command line args
     let args: Vec<String> = env::args().collect();
     
+    // Subcommands run non-interactively and exit with their own status
+    if let Some(command) = args.get(1).filter(|arg| cli::is_subcommand(arg)) {
+        std::process::exit(cli::run(command, &args[2..]));
+    }
+    
+    // --json counts without prompting and prints only the result as JSON on stdout
+    let json = args.iter().any(|arg| arg == "--json");
+    
+    if !json {
+        println!("Polycube Generator and Counter (Rust)");
+        println!("=====================================");
+    }
+    
     let mut n = 0;
-    let mut use_cache = true;
+    let mut cache_policy = CachePolicy::All;
     let mut export_csv = false;
     let mut export_text = false;
+    let mut export_json = false;
     let mut count_only = false;
     let mut operation_selected = false;
     let mut use_symmetry = true;
+    let mut verify = true;
+    let mut verify_file = None;
+    let mut bfile_range = None;
+    let mut sort_key = SortKey::default();
+    let mut threads = 0;
     
     if args.len() > 1 {
         n = args[1].parse::<u8>().unwrap_or(0);
         
         for arg in &args {
             if arg == "--no-cache" {
-                use_cache = false;
+                cache_policy = CachePolicy::None;
+            } else if arg == "--cache-final-only" {
+                cache_policy = CachePolicy::FinalOnly;
             } else if arg == "--export-csv" {
                 export_csv = true;
             } else if arg == "--export-text" {
                 export_text = true;
+            } else if arg == "--export-json" {
+                export_json = true;
             } else if arg == "--count-only" {
                 count_only = true;
                 operation_selected = true;
             } else if arg == "--generate" {
                 count_only = false;
                 operation_selected = true;
+            } else if arg == "--json" {
+                count_only = true;
+                operation_selected = true;
             } else if arg == "--no-symmetry" {
                 use_symmetry = false;
+            } else if arg == "--no-verify" {
+                verify = false;
+            }
+        }
+        
+        if let Some(index) = args.iter().position(|arg| arg == "--verify-file") {
+            verify_file = args.get(index + 1).cloned();
+        }
+        
+        if let Some(index) = args.iter().position(|arg| arg == "--bfile") {
+            let bound = |offset: usize| args.get(index + offset).and_then(|value| value.parse::<u8>().ok());
+            match (bound(1), bound(2)) {
+                (Some(from), Some(to)) if from >= 1 && from <= to => bfile_range = Some(from..=to),
+                _ => {
+                    eprintln!("Error: --bfile needs two sizes A B with 1 <= A <= B");
+                    std::process::exit(2);
+                }
+            }
+        }
+        
+        if let Some(index) = args.iter().position(|arg| arg == "--sort") {
+            let value = args.get(index + 1).map(String::as_str).unwrap_or("");
+            sort_key = cli::parse_sort_key(value).unwrap_or_else(|| {
+                eprintln!("Unknown sort key {:?}, using dimensionality", value);
+                SortKey::Dimensionality
+            });
+        }
+        
+        if let Some(index) = args.iter().position(|arg| arg == "--threads") {
+            let value = args.get(index + 1).map(String::as_str).unwrap_or("");
+            threads = cli::parse_threads(value).unwrap_or_else(|message| {
+                eprintln!("{}, using all CPUs", message);
+                0
+            });
+        }
+    }
+    
+    let threads = configure_thread_pool(threads);
+    
+    // Cross-check against a reference file of n,count lines and exit
+    if let Some(path) = verify_file {
+        println!("\nVerifying counts against {}", path);
+        
+        let rows = match verify_against(&path, use_symmetry) {
+            Ok(rows) => rows,
+            Err(e) => {
+                println!("Error reading reference file: {}", e);
+                std::process::exit(2);
             }
+        };
+        
+        println!("{:>4} {:>16} {:>16}  status", "n", "computed", "reference");
+        for (n, computed, reference, matches) in &rows {
+            println!("{:>4} {:>16} {:>16}  {}", n, computed, reference, if *matches { "ok" } else { "MISMATCH" });
+        }
+        
+        let mismatches = rows.iter().filter(|row| !row.3).count();
+        println!("{} of {} counts match", rows.len() - mismatches, rows.len());
+        
+        std::process::exit(if mismatches == 0 { 0 } else { 1 });
+    }
+    
+    // Write an OEIS b-file of counts and exit
+    if let Some(range) = bfile_range {
+        let kind = if use_symmetry { "one-sided" } else { "fixed" };
+        let path = format!("b_{}_{}_{}.txt", kind, range.start(), range.end());
+        println!("\nWriting {} counts for n={}..={} to {}", kind, range.start(), range.end(), path);
+        
+        if let Err(e) = export_bfile(range, use_symmetry, &path) {
+            println!("Error writing b-file: {}", e);
+            std::process::exit(2);
         }
+        std::process::exit(0);
+    }
+    
+    if n == 0 && json {
+        eprintln!("Error: --json needs the size on the command line, e.g. 5 --json");
+        std::process::exit(2);
     }
     
     if n == 0 {
@@ -57,7 +157,7 @@ fn main() -> io::Result<()> {
         
         n = input.trim().parse::<u8>().unwrap_or(0);
         
-        if n < 1 || n > 18 {
+        if !(1..=18).contains(&n) {
             println!("Invalid input. Using default size 5.");
             n = 5;
         }
@@ -97,50 +197,55 @@ fn main() -> io::Result<()> {
     
     // Start timing
     let start_time = Instant::now();
+    let symmetry = if use_symmetry { SymmetryMode::Rotation } else { SymmetryMode::Translation };
+    
+    if json {
+        let config = CounterConfig {
+            threads,
+            show_progress: false,
+            verify,
+            ..CounterConfig::default()
+        };
+        let (count, verification) = count_polycubes_with_report(n as usize, use_symmetry, Some(config));
+        cli::print_count_json(n, symmetry, count, verification.as_ref(), start_time.elapsed());
+        
+        let matched = verification.is_none_or(|report| report.matches());
+        std::process::exit(if matched { 0 } else { 1 });
+    }
     
     // For count-only mode, use the simplified counting algorithm
     if count_only {
         println!("\nUsing simplified counting algorithm for n={}", n);
         
         if use_symmetry {
-            println!("Counting free polycubes (accounting for symmetry)");
+            println!("Counting {} polycubes (accounting for rotations)", symmetry.kind());
         } else {
-            println!("Counting fixed polycubes (no symmetry consideration)");
+            println!("Counting {} polycubes (no symmetry consideration)", symmetry.kind());
         }
         
-        let count = count_polycubes(n as usize, use_symmetry);
+        let config = CounterConfig {
+            threads,
+            verify,
+            ..CounterConfig::default()
+        };
+        let (count, verification) = count_polycubes_with_report(n as usize, use_symmetry, Some(config));
         
         // Stop timing
         let duration = start_time.elapsed();
         
         println!("\nResults:");
         println!("=========");
-        if use_symmetry {
-            println!("Count of free polycubes of size {}: {}", n, count);
-        } else {
-            println!("Count of fixed polycubes of size {}: {}", n, count);
-        }
+        println!("Count of {} polycubes of size {}: {}", symmetry.kind(), n, count);
         println!("Time taken: {:.2} seconds", duration.as_secs_f32());
         
         // Check against known count if available
-        if let Some(expected) = get_known_count(n) {
-            println!("Expected count for size {}: {}", n, expected);
-            
-            if count != expected {
-                println!("WARNING: Count does not match expected value!");
-                println!("Difference: {}", if count > expected { 
-                    format!("+{}", count - expected) 
-                } else { 
-                    format!("-{}", expected - count) 
-                });
-            } else {
-                println!("Generated count matches expected count!");
-            }
+        if let Some(report) = verification {
+            report.print(n);
         }
     } else {
         // Generate full polycubes using the original algorithm
         println!("\nGenerating polycubes of size {}...", n);
-        let polycubes = generate_polycubes(n, use_cache);
+        let polycubes = generate_polycubes_with_policy(n, cache_policy);
         
         // Stop timing
         let duration = start_time.elapsed();
@@ -151,16 +256,8 @@ fn main() -> io::Result<()> {
         println!("Time taken: {:.2} seconds", duration.as_secs_f32());
         
         // Check against known count
-        if let Some(expected) = get_known_count(n) {
-            println!("Expected count for size {}: {}", n, expected);
-            
-            if polycubes.len() < expected as usize {
-                println!("WARNING: Missing {} polycubes!", expected as usize - polycubes.len());
-            } else if polycubes.len() > expected as usize {
-                println!("WARNING: Found {} extra polycubes!", polycubes.len() - expected as usize);
-            } else {
-                println!("Generated count matches expected count!");
-            }
+        if let Some(report) = verify.then(|| verify_one_sided_count(n, polycubes.len() as Count)).flatten() {
+            report.print(n);
         }
         
         // Generate summary statistics
@@ -168,28 +265,38 @@ fn main() -> io::Result<()> {
         
         // Export data if requested
         if export_csv {
-            match polycube_exporter::export_to_csv(&polycubes, n) {
-                Ok(filename) => println!("Exported to CSV file: {}", filename),
+            match polycube_exporter::export_to_csv(&polycubes, n, sort_key, verify) {
+                Ok(report) => println!("Exported to CSV file: {}", report.path),
                 Err(e) => println!("Error exporting to CSV: {}", e),
             }
         }
         
+        // Export to viewer JSON if requested
+        if export_json {
+            let filename = format!("polycubes_{}.json", n);
+            match polycube_exporter::export_to_viewer_json(&polycubes, n, &filename, sort_key, verify) {
+                Ok(_) => println!("Exported to JSON file: {}", filename),
+                Err(e) => println!("Error exporting to JSON: {}", e),
+            }
+        }
+        
         // Export to text file if requested
         if export_text {
-            match polycube_exporter::export_to_text_file(&polycubes, n) {
+            match polycube_exporter::export_to_text_file(&polycubes, n, sort_key, verify) {
                 Ok(_) => {},
                 Err(e) => println!("Error exporting to text file: {}", e),
             }
         }
         
         // Show available export options if not already specified
-        if !export_csv && !export_text {
+        if !export_csv && !export_text && !export_json {
             println!("\nExport options:");
             println!("  1. Export to CSV (for web viewer)");
             println!("  2. Export to text file");
-            println!("  3. Skip export");
+            println!("  3. Export to JSON (for web viewer)");
+            println!("  4. Skip export");
             
-            print!("Choose an option (1-3): ");
+            print!("Choose an option (1-4): ");
             io::stdout().flush()?;
             
             let mut input = String::new();
@@ -197,20 +304,30 @@ fn main() -> io::Result<()> {
             
             match input.trim().parse::<u8>() {
                 Ok(1) => {
-                    match polycube_exporter::export_to_csv(&polycubes, n) {
-                        Ok(filename) => {
-                            println!("Exported to CSV file: {}", filename);
+                    match polycube_exporter::export_to_csv(&polycubes, n, sort_key, verify) {
+                        Ok(report) => {
+                            println!("Exported to CSV file: {}", report.path);
                             println!("Use the 'polycube_viewer.html' file to visualize this data.");
                         },
                         Err(e) => println!("Error exporting to CSV: {}", e),
                     }
                 },
                 Ok(2) => {
-                    match polycube_exporter::export_to_text_file(&polycubes, n) {
+                    match polycube_exporter::export_to_text_file(&polycubes, n, sort_key, verify) {
                         Ok(_) => {},
                         Err(e) => println!("Error exporting to text file: {}", e),
                     }
                 },
+                Ok(3) => {
+                    let filename = format!("polycubes_{}.json", n);
+                    match polycube_exporter::export_to_viewer_json(&polycubes, n, &filename, sort_key, verify) {
+                        Ok(_) => {
+                            println!("Exported to JSON file: {}", filename);
+                            println!("Use the 'polycube-viewer.html' file to visualize this data.");
+                        },
+                        Err(e) => println!("Error exporting to JSON: {}", e),
+                    }
+                },
                 _ => {
                     println!("Skipping export.");
                 }
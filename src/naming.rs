@@ -0,0 +1,111 @@
+use crate::polycube::{Polycube, Pos};
+
+// Cube coordinates (x, y, z) in the naming table
+pub type Cell = (i8, i8, i8);
+
+// Common names of the small polycubes, one entry per one-sided shape of up to four cubes.
+// The screws are the chiral pair of twisted tetracubes; the right-handed one turns
+// x -> y -> z like a right-handed helix.
+pub const NAMED_SHAPES: [(&str, &[Cell]); 12] = [
+    ("monocube", &[(0, 0, 0)]),
+    ("dicube", &[(0, 0, 0), (1, 0, 0)]),
+    ("I-tricube", &[(0, 0, 0), (1, 0, 0), (2, 0, 0)]),
+    ("L-tricube", &[(0, 0, 0), (1, 0, 0), (0, 1, 0)]),
+    ("I-tetracube", &[(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0)]),
+    ("O-tetracube", &[(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0)]),
+    ("L-tetracube", &[(0, 0, 0), (1, 0, 0), (2, 0, 0), (0, 1, 0)]),
+    ("T-tetracube", &[(0, 0, 0), (1, 0, 0), (2, 0, 0), (1, 1, 0)]),
+    ("S-tetracube", &[(0, 0, 0), (1, 0, 0), (1, 1, 0), (2, 1, 0)]),
+    ("right-handed screw tetracube", &[(0, 0, 0), (1, 0, 0), (1, 1, 0), (1, 1, 1)]),
+    ("left-handed screw tetracube", &[(0, 0, 0), (-1, 0, 0), (-1, 1, 0), (-1, 1, 1)]),
+    ("branch tetracube", &[(0, 0, 0), (1, 0, 0), (0, 1, 0), (0, 0, 1)]),
+];
+
+// describe names a shape after a named one it contains only when at most this many
+// cubes were added; bigger shapes are described by their bounding box
+pub const DESCRIBE_MAX_ADDED: usize = 2;
+
+fn named_polycube(cubes: &[Cell]) -> Polycube {
+    Polycube::new(cubes.iter().map(|&(x, y, z)| Pos::new(x, y, z)).collect()).normalize()
+}
+
+impl Polycube {
+    // Common name of the shape, if it is one of NAMED_SHAPES in some rotation
+    pub fn common_name(&self) -> Option<&'static str> {
+        NAMED_SHAPES.iter()
+            .find(|(_, cubes)| cubes.len() == self.cubes.len() && named_polycube(cubes).is_rotation_of(self))
+            .map(|&(name, _)| name)
+    }
+
+    // Best-effort human description. Named shapes get their name with an "(exact)"
+    // qualifier. Slightly larger shapes are described by the largest named shape that
+    // shape_distance finds inside them, e.g. "T-tetracube with one cube stacked" when
+    // the added cubes lift a flat named shape into 3D. Anything else falls back to its
+    // bounding box, e.g. "3×2×2 polycube of 9 cubes".
+    pub fn describe(&self) -> String {
+        if self.cubes.is_empty() {
+            return "empty polycube".to_string();
+        }
+
+        if let Some(name) = self.common_name() {
+            return format!("{} (exact)", name);
+        }
+
+        let n = self.cubes.len();
+        // A named shape fits inside exactly when the distance is just the extra cubes.
+        // Prefer the largest one, and among those the first listed (flat shapes first).
+        let contained: Vec<(&str, Polycube)> = NAMED_SHAPES.iter()
+            .map(|&(name, cubes)| (name, named_polycube(cubes)))
+            .filter(|(_, named)| named.cubes.len() <= n && n - named.cubes.len() <= DESCRIBE_MAX_ADDED)
+            .filter(|(_, named)| self.shape_distance(named) == n - named.cubes.len())
+            .collect();
+        let largest = contained.iter().map(|(_, named)| named.cubes.len()).max();
+        let nearest = contained.into_iter().find(|(_, named)| Some(named.cubes.len()) == largest);
+
+        if let Some((name, named)) = nearest {
+            let added = n - named.cubes.len();
+            let verb = if named.is_flat() && !self.is_flat() { "stacked" } else { "added" };
+            return match added {
+                1 => format!("{} with one cube {}", name, verb),
+                _ => format!("{} with {} cubes {}", name, added, verb),
+            };
+        }
+
+        let (x, y, z) = self.normalize().get_dimensions();
+        format!("{}×{}×{} polycube of {} cubes", x, y, z, n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_polycubes_single_threaded;
+
+    fn shape(cubes: &[Cell]) -> Polycube {
+        Polycube::new(cubes.iter().map(|&(x, y, z)| Pos::new(x, y, z)).collect())
+    }
+
+    // Each of the eight tetracubes, in any orientation, gets its own exact name
+    #[test]
+    fn tetracubes_are_described_exactly() {
+        let mut names: Vec<String> = generate_polycubes_single_threaded(4).iter()
+            .map(|polycube| polycube.rotate(13).describe())
+            .collect();
+        assert!(names.iter().all(|name| name.ends_with("tetracube (exact)")), "{:?}", names);
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 8);
+        assert_eq!(shape(&[(0, 0, 0), (0, 1, 0), (0, 2, 0), (1, 1, 0)]).describe(), "T-tetracube (exact)");
+    }
+
+    #[test]
+    fn larger_shapes_are_described_by_what_they_contain() {
+        let stacked_t = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (1, 1, 0), (1, 0, 1)]);
+        assert_eq!(stacked_t.describe(), "T-tetracube with one cube stacked");
+        let long_i = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0), (4, 0, 0)]);
+        assert_eq!(long_i.describe(), "I-tetracube with one cube added");
+
+        let plate: Vec<Cell> = (0..9).map(|i| (i % 3, i / 3, 0)).collect();
+        assert_eq!(shape(&plate).describe(), "3×3×1 polycube of 9 cubes");
+    }
+}
@@ -1,9 +1,13 @@
-use rustc_hash::FxHashSet;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, Sub};
+
+use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Serialize, Deserialize};
 use smallvec::{smallvec, SmallVec};
 
 // 3D coordinate type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Pos {
     pub x: i8,
     pub y: i8,
@@ -28,26 +32,231 @@ impl Pos {
             Pos::new(self.x, self.y, self.z - 1),
         ]
     }
+
+    // Sum of the per-axis differences: the number of face steps between the two cells.
+    // (0,0,0) and (1,-2,3) are 6 apart.
+    #[inline]
+    pub fn manhattan_distance(&self, other: &Pos) -> u32 {
+        self.x.abs_diff(other.x) as u32 + self.y.abs_diff(other.y) as u32 + self.z.abs_diff(other.z) as u32
+    }
+
+    // Largest per-axis difference: the number of steps when edge and corner neighbours
+    // count too. (0,0,0) and (1,-2,3) are 3 apart.
+    #[inline]
+    pub fn chebyshev_distance(&self, other: &Pos) -> u32 {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y)).max(self.z.abs_diff(other.z)) as u32
+    }
+}
+
+// Which neighbouring cells count as connected to a cube
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Connectivity {
+    // Sharing a face: 6 neighbours, the usual polycubes
+    #[default]
+    Face,
+    // Sharing a face or an edge: 18 neighbours
+    FaceEdge,
+    // Sharing a face, an edge or a corner: all 26 neighbours
+    FaceEdgeVertex,
+}
+
+impl Connectivity {
+    // Short name, e.g. for file names and cache keys
+    pub fn name(&self) -> &'static str {
+        match self {
+            Connectivity::Face => "face",
+            Connectivity::FaceEdge => "face-edge",
+            Connectivity::FaceEdgeVertex => "face-edge-vertex",
+        }
+    }
+
+    // Offsets from a cube to its neighbours
+    pub fn offsets(&self) -> SmallVec<[Pos; 26]> {
+        Pos::new(0, 0, 0).neighbors(*self)
+    }
+}
+
+impl Pos {
+    // Positions connected to this one under the given connectivity. Face neighbours come
+    // in the same order as adjacent_positions.
+    pub fn neighbors(&self, connectivity: Connectivity) -> SmallVec<[Pos; 26]> {
+        // Number of coordinates a neighbour may differ in
+        let max_changed = match connectivity {
+            Connectivity::Face => return self.adjacent_positions().into_iter().collect(),
+            Connectivity::FaceEdge => 2,
+            Connectivity::FaceEdgeVertex => 3,
+        };
+
+        let mut neighbors = SmallVec::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let changed = (dx != 0) as u8 + (dy != 0) as u8 + (dz != 0) as u8;
+                    if changed > 0 && changed <= max_changed {
+                        neighbors.push(Pos::new(self.x + dx, self.y + dy, self.z + dz));
+                    }
+                }
+            }
+        }
+        neighbors
+    }
+}
+
+impl Add for Pos {
+    type Output = Pos;
+
+    #[inline]
+    fn add(self, other: Pos) -> Pos {
+        Pos::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Pos {
+    type Output = Pos;
+
+    #[inline]
+    fn sub(self, other: Pos) -> Pos {
+        Pos::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+// Rotation-invariant summary of a shape, for bucketing similar shapes before an exact
+// comparison. Non-isomorphic shapes can share a signature, so it is not an identity:
+// use get_canonical_hash or canonical_form to tell shapes apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ShapeSignature {
+    pub size: usize,
+    // Bounding box extents, smallest first
+    pub dimensions: [i8; 3],
+    pub surface_area: usize,
+    // Number of the 24 rotations that map the shape onto itself
+    pub symmetry_order: usize,
+}
+
+// Coordinate axis, for per-axis queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+    #[inline]
+    pub fn coordinate(&self, pos: Pos) -> i8 {
+        match self {
+            Axis::X => pos.x,
+            Axis::Y => pos.y,
+            Axis::Z => pos.z,
+        }
+    }
+
+    // The other two coordinates, in x, y, z order: where pos lands when projected along the axis
+    #[inline]
+    pub fn project(&self, pos: Pos) -> (i8, i8) {
+        match self {
+            Axis::X => (pos.y, pos.z),
+            Axis::Y => (pos.x, pos.z),
+            Axis::Z => (pos.x, pos.y),
+        }
+    }
+}
+
+// Outward direction of a cube face
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl Direction {
+    // In the order of Pos::adjacent_positions
+    pub const ALL: [Direction; 6] = [
+        Direction::PosX, Direction::NegX, Direction::PosY, Direction::NegY, Direction::PosZ, Direction::NegZ,
+    ];
+
+    // Offset to the neighbouring cell on this side
+    pub fn offset(&self) -> Pos {
+        match self {
+            Direction::PosX => Pos::new(1, 0, 0),
+            Direction::NegX => Pos::new(-1, 0, 0),
+            Direction::PosY => Pos::new(0, 1, 0),
+            Direction::NegY => Pos::new(0, -1, 0),
+            Direction::PosZ => Pos::new(0, 0, 1),
+            Direction::NegZ => Pos::new(0, 0, -1),
+        }
+    }
+
+    // Axis the face normal lies along
+    pub fn axis(&self) -> Axis {
+        match self {
+            Direction::PosX | Direction::NegX => Axis::X,
+            Direction::PosY | Direction::NegY => Axis::Y,
+            Direction::PosZ | Direction::NegZ => Axis::Z,
+        }
+    }
 }
 
+// Cube list of a polycube. With the `inline-cubes` feature shapes of up to 16 cubes are
+// stored inline, so each shape needs no heap allocation of its own; both forms serialize
+// the same way, so cache files are shared.
+#[cfg(feature = "inline-cubes")]
+pub type CubeVec = SmallVec<[Pos; 16]>;
+#[cfg(not(feature = "inline-cubes"))]
+pub type CubeVec = Vec<Pos>;
+
 // Polycube representation as a set of positions
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Polycube {
-    pub cubes: Vec<Pos>,
+    pub cubes: CubeVec,
 }
 
 impl Polycube {
     pub fn new(cubes: Vec<Pos>) -> Self {
-        Polycube { cubes }
+        Polycube { cubes: cubes.into_iter().collect() }
     }
 
-    // Get all possible positions to expand this polycube
-    pub fn get_expansion_positions(&self) -> FxHashSet<Pos> {
+    // Heap bytes held by the cube list: none for an inline list that has not spilled
+    pub fn heap_bytes(&self) -> usize {
+        #[cfg(feature = "inline-cubes")]
+        if !self.cubes.spilled() {
+            return 0;
+        }
+        self.cubes.capacity() * std::mem::size_of::<Pos>()
+    }
+
+    // The cubes in (x, y, z) lexicographic order, the order canonical_form compares in.
+    // Cubes are not kept sorted: rotation and translation would have to re-sort every time.
+    pub fn sorted_cubes(&self) -> Vec<Pos> {
+        let mut cubes = self.cubes.to_vec();
+        cubes.sort_unstable();
+        cubes
+    }
+
+    // Check whether a cube occupies the given position. Scans the cubes; build
+    // occupancy_set once instead when testing many positions.
+    pub fn contains(&self, pos: Pos) -> bool {
+        self.cubes.contains(&pos)
+    }
+
+    // Set of occupied positions, for repeated membership tests
+    pub fn occupancy_set(&self) -> FxHashSet<Pos> {
+        self.cubes.iter().copied().collect()
+    }
+
+    // Get all possible positions to expand this polycube: the empty cells connected to it
+    pub fn get_expansion_positions(&self, connectivity: Connectivity) -> FxHashSet<Pos> {
         let mut expansion_positions = FxHashSet::default();
-        let current_positions: FxHashSet<Pos> = self.cubes.iter().copied().collect();
+        let current_positions = self.occupancy_set();
 
         for &cube in &self.cubes {
-            for adj in cube.adjacent_positions() {
+            for adj in cube.neighbors(connectivity) {
                 if !current_positions.contains(&adj) {
                     expansion_positions.insert(adj);
                 }
@@ -57,39 +266,127 @@ impl Polycube {
         expansion_positions
     }
 
-    // Expand by adding a cube at the specified position
-    pub fn expand(&self, position: Pos) -> Self {
-        let mut new_cubes = self.cubes.clone();
-        new_cubes.push(position);
-        Self::new(new_cubes)
+    // Number of positions get_expansion_positions would return, without collecting them.
+    // Marks cells in a grid over the bounding box plus a one-cell border, which also
+    // replaces the hash lookups of the occupancy set.
+    pub fn expansion_position_count(&self, connectivity: Connectivity) -> usize {
+        if self.cubes.is_empty() {
+            return 0;
+        }
+        
+        // Lowest coordinate and extent per axis, border included
+        let low = Axis::ALL.map(|axis| self.cubes.iter().map(|&p| axis.coordinate(p) as i32).min().unwrap() - 1);
+        let high = Axis::ALL.map(|axis| self.cubes.iter().map(|&p| axis.coordinate(p) as i32).max().unwrap() + 1);
+        let size = [0, 1, 2].map(|i| (high[i] - low[i] + 1) as usize);
+        let index = |x: i32, y: i32, z: i32| {
+            ((x - low[0]) as usize * size[1] + (y - low[1]) as usize) * size[2] + (z - low[2]) as usize
+        };
+        
+        // 0 empty, 1 occupied, 2 empty and already counted
+        let mut grid = vec![0u8; size[0] * size[1] * size[2]];
+        for cube in &self.cubes {
+            grid[index(cube.x as i32, cube.y as i32, cube.z as i32)] = 1;
+        }
+        
+        let offsets = connectivity.offsets();
+        let mut count = 0;
+        for cube in &self.cubes {
+            for offset in &offsets {
+                let cell = &mut grid[index(
+                    cube.x as i32 + offset.x as i32,
+                    cube.y as i32 + offset.y as i32,
+                    cube.z as i32 + offset.z as i32,
+                )];
+                if *cell == 0 {
+                    *cell = 2;
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    // Expand by adding a cube at the specified position. Returns None if the position is
+    // already occupied or shares no face with the shape (an empty shape accepts any position).
+    pub fn expand(&self, position: Pos) -> Option<Self> {
+        if self.contains(position) {
+            return None;
+        }
+        if !self.cubes.is_empty() && !position.adjacent_positions().iter().any(|&adj| self.contains(adj)) {
+            return None;
+        }
+        
+        Some(self.expand_unchecked(position))
+    }
+
+    // Expand without checking the position; for positions from get_expansion_positions.
+    // An occupied position gives a shape with a duplicate cube.
+    pub fn expand_unchecked(&self, position: Pos) -> Self {
+        let mut cubes = self.cubes.clone();
+        cubes.push(position);
+        Polycube { cubes }
+    }
+
+    // Shift every cube by the given offset
+    pub fn translate(&self, offset: Pos) -> Self {
+        Self::new(self.cubes.iter().map(|&p| p + offset).collect())
+    }
+
+    // Mirror image in a plane across the axis (that coordinate negated), normalized. The
+    // three mirrors are rotations of each other, so they differ only in orientation.
+    pub fn mirror(&self, axis: Axis) -> Self {
+        let reflect = |p: &Pos| match axis {
+            Axis::X => Pos::new(-p.x, p.y, p.z),
+            Axis::Y => Pos::new(p.x, -p.y, p.z),
+            Axis::Z => Pos::new(p.x, p.y, -p.z),
+        };
+        Self::new(self.cubes.iter().map(reflect).collect()).normalize()
+    }
+
+    // The mirror image used wherever one reflection stands for all of them (x negated).
+    // Not a rotation of the original unless the shape is achiral.
+    pub fn mirror_all(&self) -> Self {
+        self.mirror(Axis::X)
+    }
+
+    // Smallest and largest coordinate on each axis, as the (min, max) corners of the
+    // bounding box; None for an empty polycube
+    pub fn bounding_box(&self) -> Option<(Pos, Pos)> {
+        let first = *self.cubes.first()?;
+        Some(self.cubes.iter().fold((first, first), |(min, max), p| (
+            Pos::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+            Pos::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+        )))
     }
 
     // Normalize so minimum coordinates are at origin
     pub fn normalize(&self) -> Self {
-        if self.cubes.is_empty() {
+        let Some((min, _)) = self.bounding_box() else {
             return self.clone();
-        }
-
-        let min_x = self.cubes.iter().map(|p| p.x).min().unwrap();
-        let min_y = self.cubes.iter().map(|p| p.y).min().unwrap();
-        let min_z = self.cubes.iter().map(|p| p.z).min().unwrap();
+        };
 
         let new_cubes = self.cubes.iter()
-            .map(|p| Pos::new(p.x - min_x, p.y - min_y, p.z - min_z))
+            .map(|p| Pos::new(p.x - min.x, p.y - min.y, p.z - min.z))
             .collect();
 
         Self::new(new_cubes)
     }
 
     // Check if polycube is face-connected
+    #[deprecated(note = "use is_connected(Connectivity::Face)")]
     pub fn is_face_connected(&self) -> bool {
+        self.is_connected(Connectivity::Face)
+    }
+
+    // Check if every cube can be reached from every other through connected neighbours
+    pub fn is_connected(&self, connectivity: Connectivity) -> bool {
         if self.cubes.len() <= 1 {
             return true;
         }
 
         let mut visited = FxHashSet::default();
         let mut queue = Vec::with_capacity(self.cubes.len());
-        let positions: FxHashSet<Pos> = self.cubes.iter().copied().collect();
+        let positions = self.occupancy_set();
 
         // Start with first cube
         queue.push(self.cubes[0]);
@@ -97,7 +394,7 @@ impl Polycube {
 
         // BFS traversal
         while let Some(current) = queue.pop() {
-            for adj in current.adjacent_positions() {
+            for adj in current.neighbors(connectivity) {
                 if positions.contains(&adj) && !visited.contains(&adj) {
                     visited.insert(adj);
                     queue.push(adj);
@@ -109,6 +406,196 @@ impl Polycube {
         visited.len() == self.cubes.len()
     }
 
+    // Split a possibly disconnected set of cubes into its face-connected pieces, each
+    // normalized, in order of their smallest cube. A connected shape gives one piece.
+    pub fn connected_components(&self) -> Vec<Polycube> {
+        face_components(&self.occupancy_set()).into_iter()
+            .map(|cells| Polycube::new(cells).normalize())
+            .collect()
+    }
+
+    // Get the cubes whose removal leaves the rest of the shape face-connected
+    // (the non-articulation points of the adjacency graph)
+    pub fn removable_cubes(&self) -> Vec<Pos> {
+        self.cubes.iter()
+            .copied()
+            .filter(|&pos| self.without(pos).is_connected(Connectivity::Face))
+            .collect()
+    }
+
+    // Remove a cube, the inverse of expand. Returns the normalized remainder, or None if
+    // the position is not occupied or removing it would disconnect the shape. Removing
+    // the only cube gives an empty polycube.
+    pub fn remove_cube(&self, pos: Pos) -> Option<Polycube> {
+        if !self.contains(pos) {
+            return None;
+        }
+        
+        let remainder = self.without(pos);
+        remainder.is_connected(Connectivity::Face).then(|| remainder.normalize())
+    }
+
+    // Copy of this polycube with the given cube removed (no connectivity check)
+    pub(crate) fn without(&self, pos: Pos) -> Self {
+        Self::new(self.cubes.iter().copied().filter(|&p| p != pos).collect())
+    }
+
+    // Count pairs of face-adjacent cubes, one from each shape, as currently positioned
+    pub fn shared_face_count(&self, other: &Polycube) -> usize {
+        let positions = self.occupancy_set();
+
+        other.cubes.iter()
+            .flat_map(|cube| cube.adjacent_positions())
+            .filter(|adj| positions.contains(adj))
+            .count()
+    }
+
+    // Check whether the two shapes, as currently positioned, share any cell
+    pub fn overlaps(&self, other: &Polycube) -> bool {
+        let positions = self.occupancy_set();
+        other.cubes.iter().any(|cube| positions.contains(cube))
+    }
+
+    // Number of exposed unit faces
+    pub fn surface_area(&self) -> usize {
+        let positions = self.occupancy_set();
+        
+        self.cubes.iter()
+            .flat_map(|cube| cube.adjacent_positions())
+            .filter(|adj| !positions.contains(adj))
+            .count()
+    }
+
+    // Perimeter, in unit edges, of the silhouette seen along `axis`: every side of a
+    // projected cell that does not touch another projected cell. Holes in the silhouette
+    // add their inner boundary. A 2×2 square and an L-tromino both have perimeter 8.
+    pub fn projection_perimeter(&self, axis: Axis) -> usize {
+        let cells: FxHashSet<(i8, i8)> = self.cubes.iter().map(|&cube| axis.project(cube)).collect();
+        
+        cells.iter()
+            .flat_map(|&(u, v)| [(u - 1, v), (u + 1, v), (u, v - 1), (u, v + 1)])
+            .filter(|cell| !cells.contains(cell))
+            .count()
+    }
+
+    // Every exposed unit face, as the cube it belongs to and the direction it faces, in
+    // sorted order. There are surface_area() of them.
+    pub fn boundary_faces(&self) -> Vec<(Pos, Direction)> {
+        let positions = self.occupancy_set();
+        
+        self.sorted_cubes().into_iter()
+            .flat_map(|cube| Direction::ALL.map(|direction| (cube, direction)))
+            .filter(|&(cube, direction)| !positions.contains(&(cube + direction.offset())))
+            .collect()
+    }
+
+    // Number of exposed faces pointing each way, in Direction::ALL order. Sums to
+    // surface_area(); a shape and its mirror across an axis swap that axis's two counts.
+    pub fn face_direction_counts(&self) -> [usize; 6] {
+        let mut counts = [0; 6];
+        for (_, direction) in self.boundary_faces() {
+            // Direction's variants are declared in Direction::ALL order
+            counts[direction as usize] += 1;
+        }
+        counts
+    }
+
+    // boundary_faces grouped into flat facets: maximal sets of faces that point the same
+    // way, lie in the same plane and join edge to edge. A single cube has six facets of
+    // one face; a domino has four of two faces along its sides and one at each end.
+    // Facets are ordered by direction, then plane, then smallest cube.
+    pub fn surface_regions(&self) -> Vec<Vec<(Pos, Direction)>> {
+        let mut planes: BTreeMap<(Direction, i8), FxHashSet<Pos>> = BTreeMap::new();
+        for (cube, direction) in self.boundary_faces() {
+            planes.entry((direction, direction.axis().coordinate(cube))).or_default().insert(cube);
+        }
+        
+        // Cubes under one plane's faces are face-adjacent exactly when their faces share an edge
+        planes.into_iter()
+            .flat_map(|((direction, _), cubes)| {
+                face_components(&cubes).into_iter()
+                    .map(move |mut region| {
+                        region.sort();
+                        region.into_iter().map(|cube| (cube, direction)).collect()
+                    })
+            })
+            .collect()
+    }
+
+    // Number of cubes with three or more face neighbours, where the shape branches.
+    // A straight line has none and the plus-shaped pentacube has one.
+    pub fn branch_point_count(&self) -> usize {
+        let positions = self.occupancy_set();
+        
+        self.cubes.iter()
+            .filter(|cube| cube.adjacent_positions().iter().filter(|adj| positions.contains(adj)).count() >= 3)
+            .count()
+    }
+
+    // Edges of a breadth-first spanning tree over face-adjacent cubes, from the smallest
+    // cube, each as (parent, child). A connected shape of n cubes has n - 1 edges.
+    pub fn spanning_tree(&self) -> Vec<(Pos, Pos)> {
+        let positions = self.occupancy_set();
+        let Some(&root) = self.cubes.iter().min() else {
+            return Vec::new();
+        };
+        
+        let mut visited = FxHashSet::default();
+        visited.insert(root);
+        let mut queue = std::collections::VecDeque::from([root]);
+        let mut edges = Vec::new();
+        while let Some(current) = queue.pop_front() {
+            for adj in current.adjacent_positions() {
+                if positions.contains(&adj) && visited.insert(adj) {
+                    edges.push((current, adj));
+                    queue.push_back(adj);
+                }
+            }
+        }
+        edges
+    }
+
+    // Check whether the face-adjacency graph is a tree: connected, with one fewer shared
+    // face than cubes. Any 2×2 square of cubes is a cycle, so such shapes are not trees.
+    pub fn is_tree(&self) -> bool {
+        let shared_faces = self.cubes.len() * 6 - self.surface_area();
+        self.is_connected(Connectivity::Face) && shared_faces / 2 + 1 == self.cubes.len()
+    }
+
+    // Longest shortest path between two cubes, in face-adjacent steps
+    pub fn diameter(&self) -> usize {
+        let positions = self.occupancy_set();
+        let mut diameter = 0;
+
+        // BFS from every cube; shapes are small enough for the quadratic cost
+        for &start in &self.cubes {
+            let mut distances = FxHashMap::default();
+            let mut frontier = vec![start];
+            distances.insert(start, 0);
+
+            let mut depth = 0;
+            while !frontier.is_empty() {
+                let mut next = Vec::new();
+                for current in frontier {
+                    for adj in current.adjacent_positions() {
+                        if positions.contains(&adj) && !distances.contains_key(&adj) {
+                            distances.insert(adj, depth + 1);
+                            next.push(adj);
+                        }
+                    }
+                }
+                if !next.is_empty() {
+                    depth += 1;
+                }
+                frontier = next;
+            }
+
+            diameter = diameter.max(depth);
+        }
+
+        diameter
+    }
+
     // Create base polycubes
     pub fn unit_cube() -> Self {
         Self::new(vec![Pos::new(0, 0, 0)])
@@ -133,8 +620,7 @@ impl Polycube {
 
     pub fn is_linear(&self) -> bool {
         let (width, height, depth) = self.get_dimensions();
-        (width == 1 && height == 1) || 
-        (width == 1 && depth == 1) || 
+        (width == 1 && (height == 1 || depth == 1)) || 
         (height == 1 && depth == 1)
     }
 
@@ -142,4 +628,736 @@ impl Polycube {
         let (width, height, depth) = self.get_dimensions();
         width == 1 || height == 1 || depth == 1
     }
-}
\ No newline at end of file
+
+    // Number of cubes in each slice perpendicular to `axis`, from the lowest coordinate up.
+    // Depends on orientation; sort the three profiles for a rotation-invariant fingerprint.
+    pub fn layer_counts(&self, axis: Axis) -> Vec<usize> {
+        let Some(min) = self.cubes.iter().map(|&p| axis.coordinate(p)).min() else {
+            return Vec::new();
+        };
+        let max = self.cubes.iter().map(|&p| axis.coordinate(p)).max().unwrap();
+        
+        let mut counts = vec![0; (max - min) as usize + 1];
+        for &cube in &self.cubes {
+            counts[(axis.coordinate(cube) - min) as usize] += 1;
+        }
+        counts
+    }
+
+    // Check that every axis-parallel line meeting the shape meets it in one contiguous
+    // run of cubes. Boxes are orthoconvex; shapes with a notch or hole along a line
+    // (a U, a ring) are not.
+    pub fn is_orthoconvex(&self) -> bool {
+        Axis::ALL.iter().all(|&axis| {
+            // Along each line, track the lowest and highest coordinate and the cube count
+            let mut lines: FxHashMap<(i8, i8), (i8, i8, usize)> = FxHashMap::default();
+            
+            for &cube in &self.cubes {
+                let line = axis.project(cube);
+                let coordinate = axis.coordinate(cube);
+                let entry = lines.entry(line).or_insert((coordinate, coordinate, 0));
+                entry.0 = entry.0.min(coordinate);
+                entry.1 = entry.1.max(coordinate);
+                entry.2 += 1;
+            }
+            
+            lines.values().all(|&(low, high, count)| (high - low) as usize + 1 == count)
+        })
+    }
+
+    // Check that the slices perpendicular to `axis`, taken in order, are nested: each is
+    // contained in the next up to a peak slice and contains the next after it, so nothing
+    // overhangs along the axis. Stacks tapering to a peak (or only widening, or only
+    // narrowing) are monotone; a stair that shifts sideways is not.
+    pub fn is_monotone(&self, axis: Axis) -> bool {
+        let Some(min) = self.cubes.iter().map(|&p| axis.coordinate(p)).min() else {
+            return true;
+        };
+        let max = self.cubes.iter().map(|&p| axis.coordinate(p)).max().unwrap();
+        
+        let mut slices = vec![FxHashSet::default(); (max - min) as usize + 1];
+        for &cube in &self.cubes {
+            slices[(axis.coordinate(cube) - min) as usize].insert(axis.project(cube));
+        }
+        
+        // Climb while slices grow, then descend while they shrink
+        let mut i = 0;
+        while i + 1 < slices.len() && slices[i].is_subset(&slices[i + 1]) {
+            i += 1;
+        }
+        while i + 1 < slices.len() && slices[i + 1].is_subset(&slices[i]) {
+            i += 1;
+        }
+        i + 1 == slices.len()
+    }
+
+    // Check whether the cubes occupy every cell of their bounding box
+    pub fn fills_bounding_box(&self) -> bool {
+        self.cubes_to_fill_box() == 0
+    }
+
+    // Number of empty cells in the bounding box: what must be added to make it solid.
+    // An L-tromino needs 1 to fill its 2×2 box.
+    pub fn cubes_to_fill_box(&self) -> usize {
+        let (width, height, depth) = self.normalize().get_dimensions();
+        width as usize * height as usize * depth as usize - self.cubes.len()
+    }
+
+    // The solid box filling this shape's bounding box, in place, so it contains the shape
+    pub fn fill_box(&self) -> Polycube {
+        let Some((min, max)) = self.bounding_box() else {
+            return Polycube::new(Vec::new());
+        };
+        
+        let cubes = (min.x..=max.x)
+            .flat_map(|x| (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| Pos::new(x, y, z))))
+            .collect();
+        Polycube::new(cubes)
+    }
+
+    // The empty cells of the bounding box, split into face-connected pieces, each
+    // normalized. A shape that fills its box has none; a U-shape has its notch.
+    pub fn bounding_box_complement(&self) -> Vec<Polycube> {
+        let occupied = self.occupancy_set();
+        let empty: FxHashSet<Pos> = self.fill_box().cubes.into_iter()
+            .filter(|pos| !occupied.contains(pos))
+            .collect();
+        
+        face_components(&empty).into_iter()
+            .map(|cells| Polycube::new(cells).normalize())
+            .collect()
+    }
+
+    // Volume of the convex hull of the corners of all cubes
+    pub fn convex_hull_volume(&self) -> f64 {
+        convex_hull_volume(&hull_candidate_corners(&self.cubes))
+    }
+
+    // Cubes whose centers lie on the boundary of the convex hull of all cube centers, at a
+    // vertex or on a face. Every cube of a flat or linear shape counts, since its hull has
+    // no inside.
+    pub fn hull_cube_count(&self) -> usize {
+        let centers: FxHashSet<Point> = self.cubes.iter()
+            .map(|cube| [cube.x as i64, cube.y as i64, cube.z as i64])
+            .collect();
+        let planes = hull_planes(&hull_candidates(&centers));
+        if planes.is_empty() {
+            return self.cubes.len();
+        }
+        
+        centers.iter()
+            .filter(|&&center| planes.iter().any(|&(normal, offset)| dot(normal, center) == offset))
+            .count()
+    }
+
+    // Cubes strictly inside the convex hull of the cube centers: 1 for a 3×3×3 block, 0
+    // for shell-like shapes
+    pub fn interior_cube_count(&self) -> usize {
+        self.cubes.len() - self.hull_cube_count()
+    }
+
+    // Cube count over convex hull volume: 1 for a solid box, lower for sprawling shapes
+    pub fn compactness(&self) -> f64 {
+        let hull_volume = self.convex_hull_volume();
+        if hull_volume == 0.0 {
+            return 0.0;
+        }
+        
+        self.cubes.len() as f64 / hull_volume
+    }
+}
+
+// Layered ASCII drawing: one block per z layer, rows from high y to low y, '#' for a
+// cube and '.' for an empty cell. The alternate form ({:#}) is one line listing the
+// sorted cubes, e.g. "(0,0,0) (1,0,0)".
+impl fmt::Display for Polycube {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((min, max)) = self.bounding_box() else {
+            return write!(f, "Empty polycube");
+        };
+        
+        if f.alternate() {
+            let cubes: Vec<String> = self.sorted_cubes().iter()
+                .map(|pos| format!("({},{},{})", pos.x, pos.y, pos.z))
+                .collect();
+            return write!(f, "{}", cubes.join(" "));
+        }
+        
+        let positions = self.occupancy_set();
+        
+        for z in min.z..=max.z {
+            writeln!(f, "Layer z={}", z)?;
+            
+            for y in (min.y..=max.y).rev() {
+                for x in min.x..=max.x {
+                    write!(f, "{}", if positions.contains(&Pos::new(x, y, z)) { '#' } else { '.' })?;
+                }
+                writeln!(f)?;
+            }
+            writeln!(f)?;
+        }
+        
+        Ok(())
+    }
+}
+
+// Split cells into face-connected components, flood-filling from the smallest unvisited
+// cell each time, so components come out in order of their smallest cell
+fn face_components(cells: &FxHashSet<Pos>) -> Vec<Vec<Pos>> {
+    let mut seeds: Vec<Pos> = cells.iter().copied().collect();
+    seeds.sort();
+    
+    let mut visited = FxHashSet::default();
+    let mut components = Vec::new();
+    
+    for seed in seeds {
+        if !visited.insert(seed) {
+            continue;
+        }
+        
+        let mut component = vec![seed];
+        let mut queue = vec![seed];
+        while let Some(current) = queue.pop() {
+            for adj in current.adjacent_positions() {
+                if cells.contains(&adj) && visited.insert(adj) {
+                    component.push(adj);
+                    queue.push(adj);
+                }
+            }
+        }
+        components.push(component);
+    }
+    
+    components
+}
+
+type Point = [i64; 3];
+
+// Cube corners that can be vertices of the convex hull
+fn hull_candidate_corners(cubes: &[Pos]) -> Vec<Point> {
+    let corners: FxHashSet<Point> = cubes.iter()
+        .flat_map(|cube| (0..8).map(move |i| [
+            cube.x as i64 + (i & 1),
+            cube.y as i64 + ((i >> 1) & 1),
+            cube.z as i64 + ((i >> 2) & 1),
+        ]))
+        .collect();
+    
+    hull_candidates(&corners)
+}
+
+// Lattice points that can be vertices of their convex hull. A point lying between two
+// others on an axis-parallel line is a convex combination of them, so only points at
+// both ends of their line along every axis are kept.
+fn hull_candidates(points: &FxHashSet<Point>) -> Vec<Point> {
+    // Lowest and highest coordinate along each axis-parallel line, keyed by the other two
+    let line_key = |p: &Point, axis: usize| [p[(axis + 1) % 3], p[(axis + 2) % 3]];
+    let mut extremes: [FxHashMap<[i64; 2], (i64, i64)>; 3] = Default::default();
+    for p in points {
+        for (axis, lines) in extremes.iter_mut().enumerate() {
+            let range = lines.entry(line_key(p, axis)).or_insert((p[axis], p[axis]));
+            range.0 = range.0.min(p[axis]);
+            range.1 = range.1.max(p[axis]);
+        }
+    }
+    
+    points.iter()
+        .filter(|p| (0..3).all(|axis| {
+            let (low, high) = extremes[axis][&line_key(p, axis)];
+            p[axis] == low || p[axis] == high
+        }))
+        .copied()
+        .collect()
+}
+
+// Volume of the convex hull of a set of points spanning three dimensions. Every plane
+// through three points with no point on its outer side is a face; each face's polygon is
+// split into a fan of triangles, and each triangle forms a tetrahedron with a fixed point
+// on the hull. Working in integers, six times the volume is exact.
+fn convex_hull_volume(points: &[Point]) -> f64 {
+    if points.len() < 4 {
+        return 0.0;
+    }
+    
+    let apex = points[0];
+    let volume_x6: i64 = hull_planes(points).into_iter()
+        .map(|(normal, offset)| face_volume_x6(points, normal, offset, apex))
+        .sum();
+    
+    volume_x6 as f64 / 6.0
+}
+
+// Planes of the faces of the convex hull, each as (normal, offset) in lowest terms with
+// every point p on the side dot(normal, p) <= offset. Points that all lie in one plane
+// give that plane, facing one or both ways; collinear points give none.
+fn hull_planes(points: &[Point]) -> FxHashSet<(Point, i64)> {
+    let mut faces = FxHashSet::default();
+    
+    for i in 0..points.len() {
+        for j in i + 1..points.len() {
+            for k in j + 1..points.len() {
+                let normal = cross(sub(points[j], points[i]), sub(points[k], points[i]));
+                if normal == [0, 0, 0] {
+                    continue;
+                }
+                let offset = dot(normal, points[i]);
+                
+                let mut above = false;
+                let mut below = false;
+                for &p in points {
+                    let side = dot(normal, p) - offset;
+                    above |= side > 0;
+                    below |= side < 0;
+                    if above && below {
+                        break;
+                    }
+                }
+                if above && below {
+                    continue;
+                }
+                
+                // Reduce the plane to lowest terms so each face is only listed once
+                let sign = if above { -1 } else { 1 };
+                let divisor = gcd(gcd(normal[0].abs(), normal[1].abs()), normal[2].abs()) * sign;
+                faces.insert((normal.map(|c| c / divisor), offset / divisor));
+            }
+        }
+    }
+    
+    faces
+}
+
+// Six times the volume of the pyramid from apex to the convex polygon where the plane
+// meets the points
+fn face_volume_x6(points: &[Point], normal: Point, offset: i64, apex: Point) -> i64 {
+    // Project onto the coordinate plane the face is least tilted against
+    let dropped = (0..3).max_by_key(|&axis| normal[axis].abs()).unwrap();
+    let (u, v) = ((dropped + 1) % 3, (dropped + 2) % 3);
+    
+    let on_face: Vec<Point> = points.iter().copied().filter(|&p| dot(normal, p) == offset).collect();
+    let polygon = convex_polygon(&on_face, u, v);
+    
+    (1..polygon.len().saturating_sub(1))
+        .map(|i| {
+            let (a, b, c) = (sub(polygon[0], apex), sub(polygon[i], apex), sub(polygon[i + 1], apex));
+            dot(a, cross(b, c)).abs()
+        })
+        .sum()
+}
+
+// Vertices of the convex hull of coplanar points, in order, using coordinates u and v
+// (Andrew's monotone chain)
+fn convex_polygon(points: &[Point], u: usize, v: usize) -> Vec<Point> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|p| (p[u], p[v]));
+    
+    let turn = |o: Point, a: Point, b: Point| {
+        (a[u] - o[u]) * (b[v] - o[v]) - (a[v] - o[v]) * (b[u] - o[u])
+    };
+    
+    let mut hull: Vec<Point> = Vec::with_capacity(sorted.len() * 2);
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2 && turn(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0 {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // The last point of each chain starts the other
+        hull.pop();
+    }
+    
+    hull
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: Point, b: Point) -> Point {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn dot(a: Point, b: Point) -> i64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(cubes: &[(i8, i8, i8)]) -> Polycube {
+        Polycube::new(cubes.iter().map(|&(x, y, z)| Pos::new(x, y, z)).collect())
+    }
+
+    // Only the two ends of a straight line can go; in a ring (a 2×2 square or the border
+    // of a 3×3 square) every cube can
+    #[test]
+    fn removable_cubes_of_line_and_loop() {
+        let line = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0), (4, 0, 0)]);
+        let mut ends = line.removable_cubes();
+        ends.sort();
+        assert_eq!(ends, [Pos::new(0, 0, 0), Pos::new(4, 0, 0)]);
+
+        let ring = shape(&[(0, 0, 0), (1, 0, 0), (1, 1, 0), (0, 1, 0)]);
+        assert_eq!(ring.removable_cubes().len(), 4);
+        let ring = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (2, 1, 0), (2, 2, 0), (1, 2, 0), (0, 2, 0), (0, 1, 0)]);
+        assert_eq!(ring.removable_cubes().len(), 8);
+    }
+
+    #[test]
+    fn translate_then_normalize_equals_normalize() {
+        let polycube = shape(&[(3, -2, 1), (4, -2, 1), (4, -1, 1), (4, -1, 2)]);
+        for offset in [Pos::new(0, 0, 0), Pos::new(5, -7, 2), Pos::new(-3, 4, -9)] {
+            let moved = polycube.translate(offset);
+            assert_eq!(moved.cubes.len(), polycube.cubes.len());
+            assert_eq!(moved.cubes[0], polycube.cubes[0] + offset);
+            assert_eq!(moved.normalize(), polycube.normalize());
+        }
+    }
+
+    // Two dominoes side by side touch along both cubes; one stacked on the other's end
+    // touches once, and a shifted copy overlaps
+    #[test]
+    fn dominoes_side_by_side_share_two_faces() {
+        let domino = shape(&[(0, 0, 0), (1, 0, 0)]);
+        let beside = domino.translate(Pos::new(0, 1, 0));
+        assert_eq!(domino.shared_face_count(&beside), 2);
+        assert_eq!(beside.shared_face_count(&domino), 2);
+        assert!(!domino.overlaps(&beside));
+
+        assert_eq!(domino.shared_face_count(&domino.translate(Pos::new(2, 0, 0))), 1);
+        assert_eq!(domino.shared_face_count(&domino.translate(Pos::new(1, 1, 0))), 1);
+        assert!(domino.overlaps(&domino.translate(Pos::new(1, 0, 0))));
+    }
+
+    #[test]
+    fn block_fills_its_box_and_l_tromino_does_not() {
+        let block: Vec<(i8, i8, i8)> = (0..8).map(|i| (i % 2, i / 2 % 2, i / 4)).collect();
+        assert!(shape(&block).fills_bounding_box());
+        assert!(shape(&[(0, 0, 0)]).fills_bounding_box());
+
+        let l_tromino = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        assert!(!l_tromino.fills_bounding_box());
+        assert_eq!(l_tromino.cubes_to_fill_box(), 1);
+    }
+
+    // A block fills its hull; the hull of an L-tromino adds half a cube across the notch
+    #[test]
+    fn block_compactness_is_one() {
+        let block: Vec<(i8, i8, i8)> = (0..8).map(|i| (i % 2, i / 2 % 2, i / 4)).collect();
+        let block = shape(&block);
+        assert!((block.convex_hull_volume() - 8.0).abs() < 1e-9);
+        assert!((block.compactness() - 1.0).abs() < 1e-9);
+
+        let l_tromino = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        assert!((l_tromino.convex_hull_volume() - 3.5).abs() < 1e-9);
+        assert!(l_tromino.compactness() < block.compactness());
+    }
+
+    #[test]
+    fn contains_present_and_absent_positions() {
+        let l_tromino = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        let occupied = l_tromino.occupancy_set();
+        for pos in [Pos::new(0, 0, 0), Pos::new(1, 0, 0), Pos::new(0, 1, 0)] {
+            assert!(l_tromino.contains(pos));
+            assert!(occupied.contains(&pos));
+        }
+        for pos in [Pos::new(1, 1, 0), Pos::new(0, 0, 1), Pos::new(-1, 0, 0)] {
+            assert!(!l_tromino.contains(pos));
+            assert!(!occupied.contains(&pos));
+        }
+        assert_eq!(occupied.len(), 3);
+    }
+
+    // An L-tetracube standing in the xz plane: three layers along x, one along y, two along z
+    #[test]
+    fn layer_counts_of_an_l_shape_differ_by_axis() {
+        let l_shape = shape(&[(2, 5, 1), (3, 5, 1), (4, 5, 1), (2, 5, 2)]);
+        assert_eq!(l_shape.layer_counts(Axis::X), [2, 1, 1]);
+        assert_eq!(l_shape.layer_counts(Axis::Y), [4]);
+        assert_eq!(l_shape.layer_counts(Axis::Z), [3, 1]);
+        assert!(shape(&[]).layer_counts(Axis::X).is_empty());
+    }
+
+    #[test]
+    fn box_is_orthoconvex_and_u_shape_is_not() {
+        let block: Vec<(i8, i8, i8)> = (0..12).map(|i| (i % 3, i / 3 % 2, i / 6)).collect();
+        assert!(shape(&block).is_orthoconvex());
+        assert!(shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]).is_orthoconvex());
+
+        let u_shape = shape(&[(0, 1, 0), (0, 0, 0), (1, 0, 0), (2, 0, 0), (2, 1, 0)]);
+        assert!(!u_shape.is_orthoconvex());
+        // The same U standing up along z
+        assert!(!u_shape.rotate(1).is_orthoconvex());
+    }
+
+    // Cubes meeting along an edge are connected from FaceEdge up, and cubes meeting at a
+    // corner only under FaceEdgeVertex, which gives more shapes
+    #[test]
+    fn connectivity_widens_with_each_mode() {
+        let offsets: Vec<usize> = [Connectivity::Face, Connectivity::FaceEdge, Connectivity::FaceEdgeVertex].iter()
+            .map(|connectivity| connectivity.offsets().len())
+            .collect();
+        assert_eq!(offsets, [6, 18, 26]);
+
+        let edge = shape(&[(0, 0, 0), (1, 1, 0)]);
+        assert!(!edge.is_connected(Connectivity::Face));
+        assert!(edge.is_connected(Connectivity::FaceEdge));
+        let corner = shape(&[(0, 0, 0), (1, 1, 1)]);
+        assert!(!corner.is_connected(Connectivity::FaceEdge));
+        assert!(corner.is_connected(Connectivity::FaceEdgeVertex));
+
+        let count = |connectivity| crate::generator::generate_polycubes_with_connectivity(3, connectivity).len();
+        assert_eq!(crate::generator::generate_polycubes_single_threaded(3).len(), 2);
+        assert!(count(Connectivity::FaceEdge) > 2);
+        assert!(count(Connectivity::FaceEdgeVertex) > count(Connectivity::FaceEdge));
+    }
+
+    #[test]
+    fn expand_rejects_occupied_and_detached_cells() {
+        let domino = shape(&[(0, 0, 0), (1, 0, 0)]);
+        assert!(domino.expand(Pos::new(0, 0, 0)).is_none());
+        assert!(domino.expand(Pos::new(1, 0, 0)).is_none());
+        assert!(domino.expand(Pos::new(1, 1, 1)).is_none());
+        assert!(domino.expand(Pos::new(3, 0, 0)).is_none());
+
+        let tromino = domino.expand(Pos::new(1, 1, 0)).unwrap();
+        assert_eq!(tromino.sorted_cubes(), [Pos::new(0, 0, 0), Pos::new(1, 0, 0), Pos::new(1, 1, 0)]);
+        assert_eq!(shape(&[]).expand(Pos::new(4, 4, 4)).unwrap().cubes.len(), 1);
+    }
+
+    // A line's end comes off; its middle is a cut vertex and an absent cell is refused
+    #[test]
+    fn remove_cube_keeps_the_shape_connected() {
+        let line = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0)]);
+        let shorter = line.remove_cube(Pos::new(0, 0, 0)).unwrap();
+        assert_eq!(shorter.sorted_cubes(), [Pos::new(0, 0, 0), Pos::new(1, 0, 0)]);
+        assert!(line.remove_cube(Pos::new(1, 0, 0)).is_none());
+        assert!(line.remove_cube(Pos::new(3, 0, 0)).is_none());
+
+        let emptied = shape(&[(5, 5, 5)]).remove_cube(Pos::new(5, 5, 5)).unwrap();
+        assert!(emptied.cubes.is_empty());
+    }
+
+    #[test]
+    fn display_draws_layers() {
+        let domino = shape(&[(0, 0, 0), (1, 0, 0)]);
+        assert_eq!(domino.to_string(), "Layer z=0\n##\n\n");
+        assert_eq!(format!("{:#}", domino), "(0,0,0) (1,0,0)");
+
+        let skew = shape(&[(0, 0, 0), (1, 0, 0), (1, 1, 0), (1, 1, 1)]);
+        assert_eq!(skew.to_string(), "Layer z=0\n.#\n##\n\nLayer z=1\n.#\n..\n\n");
+        assert_eq!(shape(&[]).to_string(), "Empty polycube");
+    }
+
+    #[test]
+    fn plus_pentacube_has_one_branch_point() {
+        let plus = shape(&[(1, 0, 0), (0, 1, 0), (1, 1, 0), (2, 1, 0), (1, 2, 0)]);
+        assert_eq!(plus.branch_point_count(), 1);
+        assert_eq!(shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0)]).branch_point_count(), 0);
+        // Every cube of a 2×2×2 block touches three others
+        let block: Vec<(i8, i8, i8)> = (0..8).map(|i| (i % 2, i / 2 % 2, i / 4)).collect();
+        assert_eq!(shape(&block).branch_point_count(), 8);
+    }
+
+    #[test]
+    fn ring_complement_is_its_center() {
+        let ring = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (2, 1, 0), (2, 2, 0), (1, 2, 0), (0, 2, 0), (0, 1, 0)]);
+        let complement = ring.bounding_box_complement();
+        assert_eq!(complement.len(), 1);
+        assert_eq!(complement[0].cubes.as_slice(), [Pos::new(0, 0, 0)]);
+
+        let block: Vec<(i8, i8, i8)> = (0..8).map(|i| (i % 2, i / 2 % 2, i / 4)).collect();
+        assert!(shape(&block).bounding_box_complement().is_empty());
+
+        // An S-tetracube leaves two separate corners empty
+        let s_shape = shape(&[(0, 0, 0), (1, 0, 0), (1, 1, 0), (2, 1, 0)]);
+        let corners = s_shape.bounding_box_complement();
+        assert_eq!(corners.len(), 2);
+        assert!(corners.iter().all(|corner| corner.cubes.len() == 1));
+    }
+
+    #[test]
+    fn separated_dominoes_split_into_two() {
+        let pair = shape(&[(0, 0, 0), (1, 0, 0), (3, 0, 0), (3, 1, 0)]);
+        let mut pieces: Vec<Vec<Pos>> = pair.connected_components().iter().map(Polycube::sorted_cubes).collect();
+        pieces.sort();
+        assert_eq!(pieces, [
+            vec![Pos::new(0, 0, 0), Pos::new(0, 1, 0)],
+            vec![Pos::new(0, 0, 0), Pos::new(1, 0, 0)],
+        ]);
+
+        assert_eq!(shape(&[(0, 0, 0), (1, 0, 0), (1, 1, 0)]).connected_components().len(), 1);
+        assert!(shape(&[]).connected_components().is_empty());
+    }
+
+    // Seen along z, a 2×2 square and an L-tromino both have 8 unit edges around them; the
+    // L's notch adds as many edges as its missing corner removes
+    #[test]
+    fn projection_perimeters_count_boundary_edges() {
+        let square = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0)]);
+        assert_eq!(square.projection_perimeter(Axis::Z), 8);
+        assert_eq!(square.projection_perimeter(Axis::X), 6);
+
+        let l_tromino = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        assert_eq!(l_tromino.projection_perimeter(Axis::Z), 8);
+        assert_eq!(l_tromino.projection_perimeter(Axis::Y), 6);
+
+        // Cubes stacked along the axis share one silhouette cell
+        let column = shape(&[(0, 0, 0), (0, 0, 1), (0, 0, 2)]);
+        assert_eq!(column.projection_perimeter(Axis::Z), 4);
+        assert_eq!(column.projection_perimeter(Axis::X), 8);
+    }
+
+    // Distances are symmetric and handle negative coordinates
+    #[test]
+    fn distances_between_positions() {
+        let origin = Pos::new(0, 0, 0);
+        let far = Pos::new(1, -2, 3);
+        assert_eq!(origin.manhattan_distance(&far), 6);
+        assert_eq!(far.manhattan_distance(&origin), 6);
+        assert_eq!(origin.chebyshev_distance(&far), 3);
+
+        let a = Pos::new(-4, -1, 2);
+        let b = Pos::new(3, -1, -2);
+        assert_eq!(a.manhattan_distance(&b), 11);
+        assert_eq!(a.chebyshev_distance(&b), 7);
+        assert_eq!(a.manhattan_distance(&a), 0);
+        assert_eq!(a.chebyshev_distance(&a), 0);
+
+        let extremes = (Pos::new(i8::MIN, 0, 0), Pos::new(i8::MAX, 0, 0));
+        assert_eq!(extremes.0.manhattan_distance(&extremes.1), 255);
+    }
+
+    // A 3×3 slab with one cube on top tapers to a peak along z; a stair whose steps shift
+    // sideways has slices that are not nested. Along x the slab's slices are all the same.
+    #[test]
+    fn pyramid_is_monotone_and_stair_is_not() {
+        let mut cells: Vec<(i8, i8, i8)> = (0..3).flat_map(|x| (0..3).map(move |y| (x, y, 0))).collect();
+        cells.push((1, 1, 1));
+        let pyramid = shape(&cells);
+        assert!(pyramid.is_monotone(Axis::Z));
+        assert!(pyramid.is_monotone(Axis::X));
+
+        let stair = shape(&[(0, 0, 0), (1, 0, 0), (1, 0, 1), (2, 0, 1), (2, 0, 2), (3, 0, 2)]);
+        assert!(!stair.is_monotone(Axis::Z));
+        assert!(!stair.is_monotone(Axis::X));
+        assert!(stair.is_monotone(Axis::Y));
+    }
+
+    // An L-tromino is one cube short of its 2×2 box, and filling the box keeps it in place
+    #[test]
+    fn l_tromino_fills_its_box_with_one_cube() {
+        let l_tromino = shape(&[(3, 1, 0), (4, 1, 0), (3, 2, 0)]);
+        assert_eq!(l_tromino.cubes_to_fill_box(), 1);
+
+        let filled = l_tromino.fill_box();
+        assert_eq!(filled.sorted_cubes(), vec![Pos::new(3, 1, 0), Pos::new(3, 2, 0), Pos::new(4, 1, 0), Pos::new(4, 2, 0)]);
+        assert!(l_tromino.cubes.iter().all(|cube| filled.contains(*cube)));
+        assert_eq!(filled.cubes_to_fill_box(), 0);
+        assert!(filled.fills_bounding_box());
+    }
+
+    // The grid count matches the collected positions for every pentacube, moved away from
+    // the origin, under each connectivity
+    #[test]
+    fn expansion_position_count_matches_the_positions() {
+        let unit = Polycube::unit_cube();
+        assert_eq!(unit.expansion_position_count(Connectivity::Face), 6);
+        assert_eq!(unit.expansion_position_count(Connectivity::FaceEdge), 18);
+        assert_eq!(unit.expansion_position_count(Connectivity::FaceEdgeVertex), 26);
+
+        for polycube in crate::generator::generate_polycubes_single_threaded(5) {
+            let moved = polycube.translate(Pos::new(-7, 3, -2));
+            for connectivity in [Connectivity::Face, Connectivity::FaceEdge, Connectivity::FaceEdgeVertex] {
+                assert_eq!(moved.expansion_position_count(connectivity), moved.get_expansion_positions(connectivity).len());
+            }
+        }
+        assert_eq!(Polycube::new(Vec::new()).expansion_position_count(Connectivity::Face), 0);
+    }
+
+    // A cube has six one-face facets; a domino joins its four long sides into two-face
+    // facets; and a U-shape's arm tips share a plane but not an edge, so stay apart
+    #[test]
+    fn surface_regions_join_coplanar_neighbouring_faces() {
+        let regions = Polycube::unit_cube().surface_regions();
+        assert_eq!(regions.len(), 6);
+        assert!(regions.iter().all(|region| region.len() == 1));
+
+        let domino = shape(&[(0, 0, 0), (1, 0, 0)]);
+        let regions = domino.surface_regions();
+        let mut sizes: Vec<usize> = regions.iter().map(Vec::len).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 1, 2, 2, 2, 2]);
+        for region in regions.iter().filter(|region| region.len() == 2) {
+            assert!(!matches!(region[0].1, Direction::PosX | Direction::NegX));
+        }
+
+        let u_shape = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (0, 1, 0), (2, 1, 0)]);
+        let regions = u_shape.surface_regions();
+        assert_eq!(regions.iter().filter(|region| region[0].1 == Direction::PosY).count(), 3);
+        assert_eq!(regions.iter().filter(|region| region[0].1 == Direction::PosZ).count(), 1);
+        assert_eq!(regions.iter().map(Vec::len).sum::<usize>(), u_shape.surface_area());
+    }
+
+    // A straight line is a tree and a 2×2 square is a 4-cycle; either way the spanning
+    // tree joins every cube with n - 1 face-adjacent edges
+    #[test]
+    fn line_is_a_tree_and_square_is_not() {
+        let line = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0)]);
+        let square = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0)]);
+        assert!(line.is_tree());
+        assert!(!square.is_tree());
+
+        for polycube in [&line, &square] {
+            let edges = polycube.spanning_tree();
+            assert_eq!(edges.len(), polycube.cubes.len() - 1);
+            let mut reached: FxHashSet<Pos> = [Pos::new(0, 0, 0)].into_iter().collect();
+            for (parent, child) in edges {
+                assert_eq!(parent.manhattan_distance(&child), 1);
+                assert!(reached.contains(&parent));
+                assert!(reached.insert(child));
+            }
+            assert_eq!(reached, polycube.occupancy_set());
+        }
+    }
+
+    // Mirroring twice across one axis gives back the normalized shape; a chiral shape's
+    // mirrors are rotations of each other but not of it, while an achiral one's are
+    #[test]
+    fn mirrors_undo_themselves_and_flip_chirality() {
+        let chiral = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (2, 1, 0), (2, 1, 1)]).translate(Pos::new(4, -2, 1));
+        for axis in Axis::ALL {
+            assert_eq!(chiral.mirror(axis).mirror(axis).sorted_cubes(), chiral.normalize().sorted_cubes());
+            assert!(!chiral.is_rotation_of(&chiral.mirror(axis)));
+            assert!(chiral.mirror(axis).is_rotation_of(&chiral.mirror_all()));
+        }
+        assert_eq!(chiral.mirror_all().sorted_cubes(), chiral.mirror(Axis::X).sorted_cubes());
+
+        let l_tromino = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        assert!(l_tromino.is_rotation_of(&l_tromino.mirror_all()));
+    }
+
+    // A cube shows one face each way and a 2×1 plate two each up and down but one at each
+    // end. Every run of cubes along an axis has one face at each end, so opposite counts
+    // agree, and the counts always sum to the surface area.
+    #[test]
+    fn face_direction_counts_split_the_surface() {
+        assert_eq!(Polycube::unit_cube().face_direction_counts(), [1; 6]);
+
+        let plate = shape(&[(0, 0, 0), (1, 0, 0)]);
+        assert_eq!(plate.face_direction_counts(), [1, 1, 2, 2, 2, 2]);
+
+        for polycube in crate::generator::generate_polycubes_single_threaded(5) {
+            let counts = polycube.face_direction_counts();
+            assert_eq!(counts.iter().sum::<usize>(), polycube.surface_area());
+            assert_eq!((counts[0], counts[2], counts[4]), (counts[1], counts[3], counts[5]));
+        }
+    }
+}
@@ -1,11 +1,15 @@
 use std::fs::File;
 use std::io::{self, BufWriter, Write};
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::Path;
 
-use rustc_hash::FxHashSet;
+use rustc_hash::FxHashMap;
 
-use crate::polycube::{Polycube, Pos};
-use crate::generator::get_known_count;
+use crate::polycube::{Axis, Connectivity, Polycube, Pos};
+use crate::generator::{
+    expand_layer, generate_streaming, verify_one_sided_count, Count, GeneratorConfig, GeneratorError, VerificationReport,
+};
 
 #[derive(Clone)]
 pub struct PolycubeMetrics {
@@ -18,107 +22,296 @@ pub struct PolycubeMetrics {
     pub volume: usize,
     pub shape_type: String,
     pub average_connectivity: f32,
+    // Cubes with three or more face neighbours (Polycube::branch_point_count)
+    pub branch_points: usize,
+    pub diameter: usize,
+    pub fills_bounding_box: bool,
+    pub is_orthoconvex: bool,
+    pub compactness: f64,
+    // Cubes per slice along x, y and z (Polycube::layer_counts)
+    pub layer_profiles: [Vec<usize>; 3],
+    // Silhouette perimeters along x, y and z (Polycube::projection_perimeter)
+    pub projection_perimeters: [usize; 3],
+    // Whether the slices along x, y and z are nested (Polycube::is_monotone)
+    pub monotone: [bool; 3],
+    // Whether the face-adjacency graph has no cycles (Polycube::is_tree)
+    pub is_tree: bool,
+    // Cubes strictly inside the convex hull of the cube centers (Polycube::interior_cube_count)
+    pub interior_cubes: usize,
+}
+
+// Order in which exporters list shapes. Ties are always broken by canonical form,
+// so the output order is deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    // Canonical form only
+    Canonical,
+    // Fewest exposed faces (most compact) first
+    SurfaceArea,
+    // Most face contacts per cube first
+    Connectivity,
+    // Shortest longest path through the shape first
+    Diameter,
+    // 1D, then 2D, then 3D shapes
+    #[default]
+    Dimensionality,
+}
+
+// File formats written by export_all
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Text,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Text => "txt",
+            ExportFormat::Json => "json",
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct CatalogEntry {
+    // 1-based rank of the shape's canonical form among all shapes in the export, so a
+    // shape gets the same ID in every run and under every sort key
+    pub id: usize,
     pub polycube: Polycube,
     pub metrics: PolycubeMetrics,
 }
 
+// What an export wrote and how its shape count compared with the known count
+#[derive(Debug, Clone)]
+pub struct ExportReport {
+    pub path: String,
+    pub shapes_written: usize,
+    pub verification: Option<VerificationReport>,
+}
+
+// What export_all wrote: one path per format, in the order the formats were given
+#[derive(Debug, Clone)]
+pub struct MultiExportReport {
+    pub paths: Vec<String>,
+    pub shapes_written: usize,
+    pub verification: Option<VerificationReport>,
+}
+
 pub struct SummaryData {
     pub linear_count: usize,
     pub planar_count: usize,
     pub three_d_count: usize,
     pub single_layer_count: usize,
     pub multi_layer_count: usize,
+    // Number of shapes with each interior cube count, by count
+    pub interior_cube_tally: BTreeMap<usize, usize>,
 }
 
-// Export polycubes to CSV format
-pub fn export_to_csv(polycubes: &[Polycube], n: u8) -> io::Result<String> {
+// Export polycubes to CSV format. With `verify` set the count is compared with the known
+// count for n, as in every export; without it the known table is not consulted at all.
+pub fn export_to_csv(polycubes: &[Polycube], n: u8, sort_key: SortKey, verify: bool) -> io::Result<ExportReport> {
     let filename = format!("polycubes_{}.csv", n);
     println!("Exporting {} polycubes to {}...", polycubes.len(), filename);
     
     // Verification against known counts
-    if let Some(expected) = get_known_count(n) {
-        println!("Expected count for n={}: {}", n, expected);
-        println!("Found count: {}", polycubes.len());
-
-        if polycubes.len() < expected as usize {
-            println!("WARNING: Missing {} polycubes!", expected as usize - polycubes.len());
-        } else if polycubes.len() > expected as usize {
-            println!("WARNING: Found {} extra polycubes!", polycubes.len() - expected as usize);
-        } else {
-            println!("Count matches expected value.");
-        }
-    }
+    let verification = verify_count(polycubes.len(), n, verify);
     
     // Calculate metrics for each polycube
-    let catalog = create_catalog(polycubes);
+    let catalog = order_polycubes(&create_catalog(polycubes), sort_key);
+    write_csv(&catalog, &filename)?;
     
-    let file = File::create(&filename)?;
+    println!("Export to CSV complete!");
+    Ok(ExportReport { path: filename, shapes_written: catalog.len(), verification })
+}
+
+// Write an ordered catalog as CSV, one row per cube
+fn write_csv(catalog: &[CatalogEntry], path: &str) -> io::Result<()> {
+    let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
     
-    // Write CSV header
-    writeln!(writer, "ID,ShapeType,DimensionX,DimensionY,DimensionZ,SurfaceArea,Connectivity,CubeX,CubeY,CubeZ")?;
+    write_csv_header(&mut writer)?;
     
     // Write each polycube with its metrics
+    for entry in catalog {
+        write_csv_entry(&mut writer, entry)?;
+    }
+    
+    writer.flush()
+}
+
+// Version of the CSV columns, written in the last column of every row. Version 1 ended at
+// CubeZ; version 2 appended BranchPoints, FillsBox, CanonicalKey and SchemaVersion, leaving
+// the positions of the version 1 columns unchanged.
+pub const CSV_SCHEMA_VERSION: u32 = 2;
+
+fn write_csv_header(writer: &mut impl Write) -> io::Result<()> {
+    // CanonicalKey (Polycube::canonical_key) is the same for a shape in every run and orientation
+    writeln!(writer, "ID,ShapeType,DimensionX,DimensionY,DimensionZ,SurfaceArea,Connectivity,CubeX,CubeY,CubeZ,BranchPoints,FillsBox,CanonicalKey,SchemaVersion")
+}
+
+// One CSV row per cube of the entry
+fn write_csv_entry(writer: &mut impl Write, entry: &CatalogEntry) -> io::Result<()> {
+    let metrics = &entry.metrics;
+    let canonical_key = entry.polycube.canonical_key();
+    
+    for pos in &entry.polycube.cubes {
+        writeln!(writer, 
+            "{},{},{},{},{},{},{:.2},{},{},{},{},{},{},{}", 
+            entry.id, 
+            metrics.shape_type,
+            metrics.dimension_x,
+            metrics.dimension_y,
+            metrics.dimension_z,
+            metrics.surface_area,
+            metrics.average_connectivity,
+            pos.x, pos.y, pos.z,
+            metrics.branch_points,
+            metrics.fills_bounding_box,
+            canonical_key,
+            CSV_SCHEMA_VERSION
+        )?;
+    }
+    Ok(())
+}
+
+// Export polycubes in the web viewer's native JSON format.
+//
+// Schema (kept in sync with polycube-viewer.html):
+//   [ { "id": 1, "type": "Flat", "cubes": [[x, y, z], ...] }, ... ]
+// `id` matches the CSV ID column and `type` is one of "Linear", "Flat" or "3D".
+pub fn export_to_viewer_json(polycubes: &[Polycube], n: u8, path: &str, sort_key: SortKey, verify: bool) -> io::Result<ExportReport> {
+    println!("Exporting {} polycubes to {}...", polycubes.len(), path);
+
+    // Verification against known counts
+    let verification = verify_count(polycubes.len(), n, verify);
+
+    let catalog = order_polycubes(&create_catalog(polycubes), sort_key);
+    write_viewer_json(&catalog, path)?;
+
+    println!("Export to JSON complete!");
+    Ok(ExportReport { path: path.to_string(), shapes_written: catalog.len(), verification })
+}
+
+// Write an ordered catalog in the viewer's JSON format
+fn write_viewer_json(catalog: &[CatalogEntry], path: &str) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "[")?;
     for (i, entry) in catalog.iter().enumerate() {
-        let metrics = &entry.metrics;
-        
-        for pos in &entry.polycube.cubes {
-            writeln!(writer, 
-                "{},{},{},{},{},{},{:.2},{},{},{}", 
-                i + 1, 
-                metrics.shape_type,
-                metrics.dimension_x,
-                metrics.dimension_y,
-                metrics.dimension_z,
-                metrics.surface_area,
-                metrics.average_connectivity,
-                pos.x, pos.y, pos.z
-            )?;
+        let separator = if i + 1 < catalog.len() { "," } else { "" };
+        writeln!(writer, "  {}{}", viewer_json_object(entry), separator)?;
+    }
+    writeln!(writer, "]")?;
+
+    writer.flush()
+}
+
+// One shape as a viewer JSON object, on one line
+fn viewer_json_object(entry: &CatalogEntry) -> String {
+    let cubes: Vec<String> = entry.polycube.cubes.iter()
+        .map(|pos| format!("[{},{},{}]", pos.x, pos.y, pos.z))
+        .collect();
+
+    format!("{{\"id\":{},\"type\":\"{}\",\"cubes\":[{}]}}", entry.id, entry.metrics.shape_type, cubes.join(","))
+}
+
+// Export the enumeration's parent -> child relationships as a Graphviz DOT file.
+// Nodes are the canonical polycubes of each size 1..=max_n; every size-(k+1) node has a
+// single edge from its minimal-canonical parent, the smallest canonical form among the
+// size-k shapes obtained by removing one of its cubes.
+pub fn export_growth_tree(max_n: u8, path: &str) -> io::Result<()> {
+    println!("Exporting growth tree up to n={} to {}...", max_n, path);
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "digraph growth_tree {{")?;
+    writeln!(writer, "  node [shape=box, fontname=\"monospace\"];")?;
+
+    if max_n == 0 {
+        writeln!(writer, "}}")?;
+        writer.flush()?;
+        return Ok(());
+    }
+
+    // Canonical forms of the previous layer, sorted so ids are stable across runs
+    let mut layer = vec![Polycube::unit_cube()];
+    let mut forms = vec![layer[0].canonical_form()];
+    write_growth_node(&mut writer, 1, 0, &forms[0])?;
+
+    for size in 2..=max_n as usize {
+        let ids: FxHashMap<&[Pos], usize> = forms.iter()
+            .enumerate()
+            .map(|(id, form)| (form.as_slice(), id))
+            .collect();
+
+        let mut children: Vec<(Vec<Pos>, Polycube)> = expand_layer(&layer).into_iter()
+            .map(|child| (child.canonical_form(), child))
+            .collect();
+        children.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (child_id, (form, _)) in children.iter().enumerate() {
+            write_growth_node(&mut writer, size, child_id, form)?;
+
+            // Link to the minimal-canonical parent, which is in the previous layer
+            let parent = Polycube::new(form.clone()).free_parent();
+            if let Some(parent_id) = parent.and_then(|p| ids.get(p.cubes.as_slice()).copied()) {
+                writeln!(writer, "  n{}_{} -> n{}_{};", size - 1, parent_id, size, child_id)?;
+            }
         }
+
+        let (next_forms, next_layer) = children.into_iter().unzip();
+        forms = next_forms;
+        layer = next_layer;
     }
-    
+
+    writeln!(writer, "}}")?;
     writer.flush()?;
-    println!("Export to CSV complete!");
-    Ok(filename)
+    println!("Export of growth tree complete!");
+    Ok(())
 }
 
-pub fn export_to_text_file(polycubes: &[Polycube], n: u8) -> io::Result<()> {
+fn write_growth_node<W: Write>(writer: &mut W, size: usize, id: usize, form: &[Pos]) -> io::Result<()> {
+    let cubes: Vec<String> = form.iter()
+        .map(|pos| format!("({},{},{})", pos.x, pos.y, pos.z))
+        .collect();
+    writeln!(writer, "  n{}_{} [label=\"n={} #{}\\n{}\"];", size, id, size, id + 1, cubes.join(" "))
+}
+
+pub fn export_to_text_file(polycubes: &[Polycube], n: u8, sort_key: SortKey, verify: bool) -> io::Result<ExportReport> {
+    let filename = format!("polycubes_{}.txt", n);
+
     if n >= 7 {
         println!("Warning: Export to text file is only available for n < 7 due to the large number of shapes.");
         println!("Found {} polycubes of size {}.", polycubes.len(), n);
-        return Ok(());
+        return Ok(ExportReport { path: filename, shapes_written: 0, verification: None });
     }
 
-    let filename = format!("polycubes_{}.txt", n);
     println!("Exporting {} polycubes to {}...", polycubes.len(), filename);
 
     // Verification against known counts
-    if let Some(expected) = get_known_count(n) {
-        println!("Expected count for n={}: {}", n, expected);
-        println!("Found count: {}", polycubes.len());
-
-        if polycubes.len() < expected as usize {
-            println!("WARNING: Missing {} polycubes!", expected as usize - polycubes.len());
-        } else if polycubes.len() > expected as usize {
-            println!("WARNING: Found {} extra polycubes!", polycubes.len() - expected as usize);
-        } else {
-            println!("Count matches expected value.");
-        }
-    }
+    let verification = verify_count(polycubes.len(), n, verify);
 
-    let file = File::create(&filename)?;
+    let shapes_written = write_text_report(&create_catalog(polycubes), n, sort_key, &filename)?;
+
+    println!("Export to text file complete!");
+    Ok(ExportReport { path: filename, shapes_written, verification })
+}
+
+// Write the text report for a catalog, returning the number of shapes listed
+fn write_text_report(catalog_entries: &[CatalogEntry], n: u8, sort_key: SortKey, path: &str) -> io::Result<usize> {
+    let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
 
-    writeln!(writer, "Polycubes of size {} - Total count: {}", n, polycubes.len())?;
+    writeln!(writer, "Polycubes of size {} - Total count: {}", n, catalog_entries.len())?;
     writeln!(writer, "{}", "=".repeat(50))?;
 
     // Calculate summary data
-    let catalog_entries = create_catalog(polycubes);
-    let summary = get_summary_data(&catalog_entries);
+    let summary = get_summary_data(catalog_entries);
 
     // Write summary information
     writeln!(writer, "Summary Information:")?;
@@ -129,8 +322,14 @@ pub fn export_to_text_file(polycubes: &[Polycube], n: u8) -> io::Result<()> {
     writeln!(writer, "  Single-layer shapes: {}", summary.single_layer_count)?;
     writeln!(writer, "  Multi-layer shapes: {}", summary.multi_layer_count)?;
     writeln!(writer)?;
+    for (interior_cubes, count) in &summary.interior_cube_tally {
+        writeln!(writer, "  Shapes with {} interior cubes: {}", interior_cubes, count)?;
+    }
+    writeln!(writer)?;
 
-    let ordered_polycubes = order_polycubes(&catalog_entries);
+    // Shapes stay grouped by dimensionality; the sort key orders them within each group
+    let mut ordered_polycubes = order_polycubes(catalog_entries, sort_key);
+    ordered_polycubes.sort_by_key(|entry| get_dimensionality_order(&entry.metrics));
     writeln!(writer, "Shapes organized systematically ({} total)", ordered_polycubes.len())?;
     writeln!(writer, "{}", "-".repeat(50))?;
     writeln!(writer)?;
@@ -138,7 +337,7 @@ pub fn export_to_text_file(polycubes: &[Polycube], n: u8) -> io::Result<()> {
     let mut current_dimension = -1;
     let mut current_shape_type = String::new();
 
-    for (i, entry) in ordered_polycubes.iter().enumerate() {
+    for entry in &ordered_polycubes {
         let dimensionality = get_dimensionality_order(&entry.metrics);
 
         // Print section headers
@@ -164,39 +363,212 @@ pub fn export_to_text_file(polycubes: &[Polycube], n: u8) -> io::Result<()> {
         }
 
         writeln!(writer)?;
-        writeln!(writer, "Polycube #{}", i + 1)?;
+        writeln!(writer, "Polycube #{}: {}", entry.id, entry.polycube.describe())?;
         writeln!(writer, "Type: {}, Dimensions: {}×{}×{}", 
             entry.metrics.shape_type, 
             entry.metrics.dimension_x, 
             entry.metrics.dimension_y, 
             entry.metrics.dimension_z)?;
         
-        writeln!(writer, "S/V Ratio: {:.2}, Connectivity: {:.1}", 
+        writeln!(writer, "S/V Ratio: {:.2}, Connectivity: {:.1}, Branch points: {}, Compactness: {:.2}, Interior cubes: {}", 
             entry.metrics.surface_area as f32 / entry.metrics.volume as f32,
-            entry.metrics.average_connectivity)?;
+            entry.metrics.average_connectivity,
+            entry.metrics.branch_points,
+            entry.metrics.compactness,
+            entry.metrics.interior_cubes)?;
+
+        let profiles: Vec<String> = entry.metrics.layer_profiles.iter()
+            .map(|counts| counts.iter().map(usize::to_string).collect::<Vec<_>>().join("-"))
+            .collect();
+        writeln!(writer, "Layers: x {}, y {}, z {}, Orthoconvex: {}, Tree: {}", profiles[0], profiles[1], profiles[2],
+            if entry.metrics.is_orthoconvex { "yes" } else { "no" },
+            if entry.metrics.is_tree { "yes" } else { "no" })?;
+        let [perimeter_x, perimeter_y, perimeter_z] = entry.metrics.projection_perimeters;
+        writeln!(writer, "Projection perimeters: x {}, y {}, z {}", perimeter_x, perimeter_y, perimeter_z)?;
+        let monotone: Vec<&str> = ["x", "y", "z"].into_iter()
+            .zip(entry.metrics.monotone)
+            .filter_map(|(name, monotone)| monotone.then_some(name))
+            .collect();
+        writeln!(writer, "Monotone along: {}", if monotone.is_empty() { "none".to_string() } else { monotone.join(", ") })?;
 
         // Write cubes in the polycube
-        let mut cube_strs = Vec::new();
-        for pos in &entry.polycube.cubes {
-            cube_strs.push(format!("({},{},{})", pos.x, pos.y, pos.z));
-        }
-        cube_strs.sort();
+        let cube_strs: Vec<String> = entry.polycube.sorted_cubes().iter()
+            .map(|pos| format!("({},{},{})", pos.x, pos.y, pos.z))
+            .collect();
         writeln!(writer, "Cubes: {}", cube_strs.join(", "))?;
 
         // Write ASCII representation of the polycube
-        writeln!(writer, "{}", polycube_to_string(&entry.polycube))?;
+        writeln!(writer, "{}", entry.polycube)?;
         writeln!(writer, "{}", "-".repeat(40))?;
     }
 
     writer.flush()?;
-    println!("Export to text file complete!");
-    Ok(())
+    Ok(ordered_polycubes.len())
+}
+
+// Export to several formats at once, computing the metrics only once. Files are named
+// polycubes_{n}.{ext} inside dir. Returns the paths written and the count check; on
+// failure, stops at the first error, whose message lists the files already written. As
+// with export_to_text_file, the text format is skipped for n >= 7, and `verify` compares
+// the count with the known count.
+pub fn export_all(
+    polycubes: &[Polycube],
+    n: u8,
+    dir: &str,
+    formats: &[ExportFormat],
+    sort_key: SortKey,
+    verify: bool,
+) -> io::Result<MultiExportReport> {
+    println!("Exporting {} polycubes to {} format(s) in {}...", polycubes.len(), formats.len(), dir);
+    let verification = verify_count(polycubes.len(), n, verify);
+
+    let catalog = create_catalog(polycubes);
+    let ordered = order_polycubes(&catalog, sort_key);
+    let mut written = Vec::new();
+
+    for &format in formats {
+        let path = Path::new(dir).join(format!("polycubes_{}.{}", n, format.extension()))
+            .to_string_lossy()
+            .into_owned();
+
+        let result = match format {
+            ExportFormat::Csv => write_csv(&ordered, &path),
+            ExportFormat::Json => write_viewer_json(&ordered, &path),
+            ExportFormat::Text if n >= 7 => {
+                println!("Warning: Skipping text export, which is only available for n < 7.");
+                continue;
+            }
+            ExportFormat::Text => write_text_report(&catalog, n, sort_key, &path).map(|_| ()),
+        };
+
+        if let Err(e) = result {
+            return Err(io::Error::new(e.kind(), format!(
+                "writing {}: {} (already written: {})", path, e,
+                if written.is_empty() { "none".to_string() } else { written.join(", ") })));
+        }
+        written.push(path);
+    }
+
+    println!("Export complete!");
+    Ok(MultiExportReport { paths: written, shapes_written: catalog.len(), verification })
+}
+
+// Generate the polycubes of size n and write each one as soon as it is found, never holding
+// the whole set (see generate_streaming). Csv writes the rows of export_to_csv; Json writes
+// JSON lines, one viewer JSON object per line with no enclosing array. IDs are in the order
+// shapes were found, so unlike the other exports they differ between runs. Text is
+// rejected, since its report starts with a summary of the whole set. Returns the number of
+// shapes written.
+pub fn generate_and_export(n: u8, format: ExportFormat, path: &str, config: &GeneratorConfig) -> Result<u64, GeneratorError> {
+    if format == ExportFormat::Text {
+        return Err(GeneratorError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "text reports need the whole set; stream csv or json instead")));
+    }
+    
+    let mut writer = BufWriter::new(File::create(path).map_err(GeneratorError::Io)?);
+    if format == ExportFormat::Csv {
+        write_csv_header(&mut writer).map_err(GeneratorError::Io)?;
+    }
+    
+    let mut next_id = 1;
+    let written = generate_streaming(n, config, |polycube| {
+        let entry = CatalogEntry { id: next_id, polycube: polycube.clone(), metrics: calculate_metrics(polycube) };
+        next_id += 1;
+        match format {
+            ExportFormat::Csv => write_csv_entry(&mut writer, &entry),
+            ExportFormat::Json => writeln!(writer, "{}", viewer_json_object(&entry)),
+            ExportFormat::Text => unreachable!("text is rejected above"),
+        }.map_err(GeneratorError::Io)
+    })?;
+    writer.flush().map_err(GeneratorError::Io)?;
+    
+    println!("Streamed {} polycubes to {}", written, path);
+    Ok(written)
+}
+
+// Export one shape on its own, e.g. a problem shape picked out of a larger result.
+// The file has the same layout as a full export with a single entry (ID 1).
+pub fn export_single(polycube: &Polycube, format: ExportFormat, path: &str) -> io::Result<()> {
+    let catalog = create_catalog(std::slice::from_ref(polycube));
+
+    match format {
+        ExportFormat::Csv => write_csv(&catalog, path),
+        ExportFormat::Json => write_viewer_json(&catalog, path),
+        ExportFormat::Text => {
+            write_text_report(&catalog, polycube.cubes.len() as u8, SortKey::default(), path).map(|_| ())
+        }
+    }
+}
+
+// Read shapes from a plain coordinate file: one `x y z` cube per line, blank lines between
+// shapes and `#` comment lines ignored. Each shape is normalized; a malformed line or a
+// disconnected shape is an InvalidData error naming the line or the shape index (from 0).
+pub fn import_from_coords(path: &str) -> io::Result<Vec<Polycube>> {
+    let contents = std::fs::read_to_string(path)?;
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    
+    let mut shapes: Vec<Vec<Pos>> = Vec::new();
+    let mut current = Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            if !current.is_empty() {
+                shapes.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        
+        let malformed = || invalid(format!("{}:{}: expected `x y z`, found `{}`", path, line_number + 1, line));
+        let coords = line.split_whitespace()
+            .map(|value| value.parse::<i8>().map_err(|_| malformed()))
+            .collect::<io::Result<Vec<i8>>>()?;
+        let [x, y, z] = coords[..] else {
+            return Err(malformed());
+        };
+        current.push(Pos::new(x, y, z));
+    }
+    if !current.is_empty() {
+        shapes.push(current);
+    }
+    
+    shapes.into_iter().enumerate().map(|(index, cubes)| {
+        let polycube = Polycube::new(cubes).normalize();
+        if polycube.occupancy_set().len() != polycube.cubes.len() {
+            return Err(invalid(format!("{}: shape {} repeats a cube", path, index)));
+        }
+        if !polycube.is_connected(Connectivity::Face) {
+            return Err(invalid(format!("{}: shape {} is not connected", path, index)));
+        }
+        Ok(polycube)
+    }).collect()
+}
+
+// Compare the found count against the known count for n; printing the outcome is left to
+// the caller. Without `verify`, nothing is looked up.
+fn verify_count(found: usize, n: u8, verify: bool) -> Option<VerificationReport> {
+    if !verify {
+        return None;
+    }
+    
+    verify_one_sided_count(n, found as Count)
 }
 
+// Number shapes in canonical form order before any sort key is applied. Generation order
+// depends on threading, canonical forms do not.
 fn create_catalog(polycubes: &[Polycube]) -> Vec<CatalogEntry> {
-    polycubes.iter().map(|polycube| {
+    let mut keyed: Vec<(Vec<Pos>, &Polycube)> = polycubes.iter()
+        .map(|polycube| (polycube.canonical_form(), polycube))
+        .collect();
+    keyed.sort_by(|(form_a, _), (form_b, _)| form_a.cmp(form_b));
+
+    keyed.into_iter().enumerate().map(|(i, (_, polycube))| {
         let metrics = calculate_metrics(polycube);
         CatalogEntry {
+            id: i + 1,
             polycube: polycube.clone(),
             metrics,
         }
@@ -204,27 +576,22 @@ fn create_catalog(polycubes: &[Polycube]) -> Vec<CatalogEntry> {
 }
 
 fn calculate_metrics(polycube: &Polycube) -> PolycubeMetrics {
-    let min_x = polycube.cubes.iter().map(|p| p.x).min().unwrap_or(0);
-    let max_x = polycube.cubes.iter().map(|p| p.x).max().unwrap_or(0);
-    let min_y = polycube.cubes.iter().map(|p| p.y).min().unwrap_or(0);
-    let max_y = polycube.cubes.iter().map(|p| p.y).max().unwrap_or(0);
-    let min_z = polycube.cubes.iter().map(|p| p.z).min().unwrap_or(0);
-    let max_z = polycube.cubes.iter().map(|p| p.z).max().unwrap_or(0);
+    let origin = Pos::new(0, 0, 0);
+    let (min, max) = polycube.bounding_box().unwrap_or((origin, origin));
     
-    let dimension_x = max_x - min_x + 1;
-    let dimension_y = max_y - min_y + 1;
-    let dimension_z = max_z - min_z + 1;
+    let dimension_x = max.x - min.x + 1;
+    let dimension_y = max.y - min.y + 1;
+    let dimension_z = max.z - min.z + 1;
     
     // Check if it's a linear shape
-    let is_linear = dimension_x == 1 && dimension_y == 1 || 
-                    dimension_x == 1 && dimension_z == 1 || 
+    let is_linear = dimension_x == 1 && (dimension_y == 1 || dimension_z == 1) || 
                     dimension_y == 1 && dimension_z == 1;
     
     // Check if it's a flat shape
     let is_flat = dimension_x == 1 || dimension_y == 1 || dimension_z == 1;
     
     // Calculate surface area (count of exposed faces)
-    let positions: FxHashSet<_> = polycube.cubes.iter().copied().collect();
+    let positions = polycube.occupancy_set();
     let mut surface_area = 0;
     
     for pos in &positions {
@@ -260,6 +627,16 @@ fn calculate_metrics(polycube: &Polycube) -> PolycubeMetrics {
         volume: polycube.cubes.len(),
         shape_type,
         average_connectivity: avg_connectivity,
+        branch_points: polycube.branch_point_count(),
+        diameter: polycube.diameter(),
+        fills_bounding_box: polycube.cubes.len() == dimension_x as usize * dimension_y as usize * dimension_z as usize,
+        is_orthoconvex: polycube.is_orthoconvex(),
+        compactness: polycube.compactness(),
+        layer_profiles: Axis::ALL.map(|axis| polycube.layer_counts(axis)),
+        projection_perimeters: Axis::ALL.map(|axis| polycube.projection_perimeter(axis)),
+        monotone: Axis::ALL.map(|axis| polycube.is_monotone(axis)),
+        is_tree: polycube.is_tree(),
+        interior_cubes: polycube.interior_cube_count(),
     }
 }
 
@@ -267,8 +644,11 @@ fn get_summary_data(catalog: &[CatalogEntry]) -> SummaryData {
     let mut linear_count = 0;
     let mut planar_count = 0;
     let mut single_layer_count = 0;
+    let mut interior_cube_tally = BTreeMap::new();
     
     for entry in catalog {
+        *interior_cube_tally.entry(entry.metrics.interior_cubes).or_insert(0) += 1;
+        
         if entry.metrics.is_linear {
             linear_count += 1;
         } else if entry.metrics.is_flat {
@@ -288,29 +668,29 @@ fn get_summary_data(catalog: &[CatalogEntry]) -> SummaryData {
         three_d_count: catalog.len() - linear_count - planar_count,
         single_layer_count,
         multi_layer_count: catalog.len() - single_layer_count,
+        interior_cube_tally,
     }
 }
 
-fn order_polycubes(catalog: &[CatalogEntry]) -> Vec<CatalogEntry> {
+fn order_polycubes(catalog: &[CatalogEntry], sort_key: SortKey) -> Vec<CatalogEntry> {
     let mut ordered = catalog.to_vec();
+
     ordered.sort_by(|a, b| {
-        let dim_a = get_dimensionality_order(&a.metrics);
-        let dim_b = get_dimensionality_order(&b.metrics);
-        
-        if dim_a != dim_b {
-            return dim_a.cmp(&dim_b);
-        }
-        
-        // Then sort by shape type
-        let type_cmp = a.metrics.shape_type.cmp(&b.metrics.shape_type);
-        if type_cmp != Ordering::Equal {
-            return type_cmp;
-        }
-        
-        // Finally sort by volume
-        a.metrics.volume.cmp(&b.metrics.volume)
+        let primary = match sort_key {
+            SortKey::Canonical => Ordering::Equal,
+            SortKey::SurfaceArea => a.metrics.surface_area.cmp(&b.metrics.surface_area),
+            SortKey::Connectivity => b.metrics.average_connectivity.total_cmp(&a.metrics.average_connectivity),
+            SortKey::Diameter => a.metrics.diameter.cmp(&b.metrics.diameter),
+            SortKey::Dimensionality => get_dimensionality_order(&a.metrics)
+                .cmp(&get_dimensionality_order(&b.metrics))
+                .then_with(|| a.metrics.shape_type.cmp(&b.metrics.shape_type))
+                .then_with(|| a.metrics.volume.cmp(&b.metrics.volume)),
+        };
+
+        // IDs follow canonical form order, the deterministic tie-break for every key
+        primary.then_with(|| a.id.cmp(&b.id))
     });
-    
+
     ordered
 }
 
@@ -324,40 +704,553 @@ fn get_dimensionality_order(metrics: &PolycubeMetrics) -> i32 {
     3 // 3D
 }
 
-fn polycube_to_string(polycube: &Polycube) -> String {
-    if polycube.cubes.is_empty() {
-        return String::from("Empty polycube");
+// Side of the square GIF frames, in pixels
+#[cfg(feature = "gif")]
+const GIF_SIZE: u16 = 256;
+
+// Time each GIF frame is shown, in hundredths of a second
+#[cfg(feature = "gif")]
+const GIF_FRAME_DELAY: u16 = 5;
+
+// Palette entries: background, edges, then shades of blue from dark to light
+#[cfg(feature = "gif")]
+const GIF_SHADES: usize = 30;
+
+// A unit face in 3D: four corners and the outward normal
+#[cfg(feature = "gif")]
+type Face = ([[f32; 3]; 4], [f32; 3]);
+
+// A face projected to pixel coordinates
+#[cfg(feature = "gif")]
+type ScreenQuad = [(f32, f32); 4];
+
+// Write an animated GIF of the shape turning once about its vertical (z) axis, seen from
+// 30° above. Frame i shows a turn of i/frames of a full circle, so the loop is seamless.
+// Faces are shaded by a light fixed to the viewer and drawn far to near.
+#[cfg(feature = "gif")]
+pub fn export_rotation_gif(polycube: &Polycube, path: &str, frames: usize) -> io::Result<()> {
+    if frames == 0 || polycube.cubes.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "a rotation GIF needs a shape and at least one frame"));
     }
-    
-    let min_x = polycube.cubes.iter().map(|p| p.x).min().unwrap();
-    let max_x = polycube.cubes.iter().map(|p| p.x).max().unwrap();
-    let min_y = polycube.cubes.iter().map(|p| p.y).min().unwrap();
-    let max_y = polycube.cubes.iter().map(|p| p.y).max().unwrap();
-    let min_z = polycube.cubes.iter().map(|p| p.z).min().unwrap();
-    let max_z = polycube.cubes.iter().map(|p| p.z).max().unwrap();
-    
-    // Convert to a set for quick lookup
-    let positions: FxHashSet<_> = polycube.cubes.iter().copied().collect();
-    
-    // Create ASCII representation
-    let mut result = String::new();
-    
-    // For a simple representation, we'll draw layers from top to bottom
-    for z in min_z..=max_z {
-        result.push_str(&format!("Layer z={}\n", z));
-        
-        for y in (min_y..=max_y).rev() {
-            for x in min_x..=max_x {
-                if positions.contains(&Pos::new(x, y, z)) {
-                    result.push('#');
-                } else {
-                    result.push('.');
+
+    let mut palette = vec![255, 255, 255, 40, 40, 48];
+    for shade in 0..GIF_SHADES {
+        let level = shade as f32 / (GIF_SHADES - 1) as f32;
+        palette.extend([(40.0 + 150.0 * level) as u8, (70.0 + 150.0 * level) as u8, (140.0 + 115.0 * level) as u8]);
+    }
+
+    let faces = exposed_faces(polycube);
+    // Every corner stays within this distance of the centre, whatever the angle
+    let radius = faces.iter()
+        .flat_map(|(corners, _)| corners.iter())
+        .map(|c| (c[0] * c[0] + c[1] * c[1] + c[2] * c[2]).sqrt())
+        .fold(0.5, f32::max);
+    let scale = (GIF_SIZE as f32 / 2.0 - 8.0) / radius;
+
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = gif::Encoder::new(writer, GIF_SIZE, GIF_SIZE, &palette).map_err(io::Error::other)?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(io::Error::other)?;
+
+    for i in 0..frames {
+        let angle = std::f32::consts::TAU * i as f32 / frames as f32;
+        let pixels = render_rotation_frame(&faces, angle, scale);
+        let mut frame = gif::Frame::from_indexed_pixels(GIF_SIZE, GIF_SIZE, pixels, None);
+        frame.delay = GIF_FRAME_DELAY;
+        encoder.write_frame(&frame).map_err(io::Error::other)?;
+    }
+
+    Ok(())
+}
+
+// The unit faces not covered by another cube, centred on the shape's centroid
+#[cfg(feature = "gif")]
+fn exposed_faces(polycube: &Polycube) -> Vec<Face> {
+    let occupied = polycube.occupancy_set();
+    let count = polycube.cubes.len() as f32;
+    let centroid = [
+        polycube.cubes.iter().map(|p| p.x as f32).sum::<f32>() / count + 0.5,
+        polycube.cubes.iter().map(|p| p.y as f32).sum::<f32>() / count + 0.5,
+        polycube.cubes.iter().map(|p| p.z as f32).sum::<f32>() / count + 0.5,
+    ];
+
+    let mut faces = Vec::new();
+    for &cube in &polycube.cubes {
+        for axis in 0..3 {
+            for side in [0, 1] {
+                let mut offset = [0i8; 3];
+                offset[axis] = if side == 1 { 1 } else { -1 };
+                if occupied.contains(&(cube + Pos::new(offset[0], offset[1], offset[2]))) {
+                    continue;
                 }
+
+                // The face lies in the plane axis = cube + side, spanned by the other two axes
+                let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                let base = [cube.x as f32, cube.y as f32, cube.z as f32];
+                let corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(du, dv)| {
+                    let mut corner = base;
+                    corner[axis] += side as f32;
+                    corner[u] += du;
+                    corner[v] += dv;
+                    [corner[0] - centroid[0], corner[1] - centroid[1], corner[2] - centroid[2]]
+                });
+                let mut normal = [0.0; 3];
+                normal[axis] = offset[axis] as f32;
+                faces.push((corners, normal));
             }
-            result.push('\n');
         }
-        result.push('\n');
     }
-    
-    result
-}
\ No newline at end of file
+    faces
+}
+
+// Draw the faces turned by angle about z into a GIF_SIZE square of palette indices
+#[cfg(feature = "gif")]
+fn render_rotation_frame(faces: &[Face], angle: f32, scale: f32) -> Vec<u8> {
+    let (sin, cos) = angle.sin_cos();
+    let (elevation_sin, elevation_cos) = 30f32.to_radians().sin_cos();
+    let rotate = |p: [f32; 3]| [p[0] * cos - p[1] * sin, p[0] * sin + p[1] * cos, p[2]];
+    // The viewer looks along +y and down; depth grows away from the viewer
+    let depth = |p: [f32; 3]| p[1] * elevation_cos - p[2] * elevation_sin;
+    let light = [-0.4, -0.6, 0.7];
+    let light_length = (0.16f32 + 0.36 + 0.49).sqrt();
+    let half = GIF_SIZE as f32 / 2.0;
+
+    let mut canvas = Canvas {
+        pixels: vec![0; GIF_SIZE as usize * GIF_SIZE as usize],
+        depths: vec![f32::INFINITY; GIF_SIZE as usize * GIF_SIZE as usize],
+    };
+    let mut outlines = Vec::new();
+
+    for (corners, normal) in faces {
+        let normal = rotate(*normal);
+        if depth(normal) >= 0.0 {
+            continue;
+        }
+
+        let corners = corners.map(rotate);
+        let screen = corners.map(|c| {
+            let up = c[2] * elevation_cos + c[1] * elevation_sin;
+            (half + c[0] * scale, half - up * scale)
+        });
+        let corner_depths = corners.map(depth);
+        let lit = (normal[0] * light[0] + normal[1] * light[1] + normal[2] * light[2]) / light_length;
+        let brightness = 0.3 + 0.7 * lit.max(0.0);
+        canvas.fill_quad(&screen, &corner_depths, 2 + (brightness * (GIF_SHADES - 1) as f32).round() as u8);
+        outlines.push((screen, corner_depths));
+    }
+
+    // Edges go on once every face is in the depth buffer, so hidden ones stay hidden
+    for (screen, corner_depths) in &outlines {
+        for i in 0..4 {
+            let j = (i + 1) % 4;
+            canvas.draw_line(screen[i], screen[j], corner_depths[i], corner_depths[j], 1);
+        }
+    }
+    canvas.pixels
+}
+
+// Palette indices with a depth buffer, GIF_SIZE pixels square
+#[cfg(feature = "gif")]
+struct Canvas {
+    pixels: Vec<u8>,
+    depths: Vec<f32>,
+}
+
+#[cfg(feature = "gif")]
+impl Canvas {
+    // Fill a convex quadrilateral, given in either winding order, where it is nearer than
+    // what is already drawn. Depth is interpolated over the face's plane.
+    fn fill_quad(&mut self, corners: &ScreenQuad, corner_depths: &[f32; 4], color: u8) {
+        let size = GIF_SIZE as usize;
+        let (x0, y0, d0) = (corners[0].0, corners[0].1, corner_depths[0]);
+        let e1 = (corners[1].0 - x0, corners[1].1 - y0, corner_depths[1] - d0);
+        let e2 = (corners[3].0 - x0, corners[3].1 - y0, corner_depths[3] - d0);
+        let determinant = e1.0 * e2.1 - e1.1 * e2.0;
+        if determinant.abs() < 1e-3 {
+            // Seen edge-on
+            return;
+        }
+        let depth_dx = (e1.2 * e2.1 - e2.2 * e1.1) / determinant;
+        let depth_dy = (e1.0 * e2.2 - e2.0 * e1.2) / determinant;
+
+        let low_x = corners.iter().map(|c| c.0).fold(f32::MAX, f32::min).max(0.0) as usize;
+        let high_x = (corners.iter().map(|c| c.0).fold(f32::MIN, f32::max).ceil() as usize).min(size);
+        let low_y = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min).max(0.0) as usize;
+        let high_y = (corners.iter().map(|c| c.1).fold(f32::MIN, f32::max).ceil() as usize).min(size);
+
+        for y in low_y..high_y {
+            for x in low_x..high_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let (mut positive, mut negative) = (false, false);
+                for i in 0..4 {
+                    let (a, b) = (corners[i], corners[(i + 1) % 4]);
+                    let side = (b.0 - a.0) * (py - a.1) - (b.1 - a.1) * (px - a.0);
+                    positive |= side > 0.0;
+                    negative |= side < 0.0;
+                }
+                let depth = d0 + depth_dx * (px - x0) + depth_dy * (py - y0);
+                if !(positive && negative) && depth < self.depths[y * size + x] {
+                    self.pixels[y * size + x] = color;
+                    self.depths[y * size + x] = depth;
+                }
+            }
+        }
+    }
+
+    // Draw a line where it is not behind a face, with a little slack so a face's own
+    // edges are drawn
+    fn draw_line(&mut self, from: (f32, f32), to: (f32, f32), from_depth: f32, to_depth: f32, color: u8) {
+        let size = GIF_SIZE as usize;
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let (x, y) = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+            let depth = from_depth + (to_depth - from_depth) * t;
+            if x >= 0.0 && y >= 0.0 && (x as usize) < size && (y as usize) < size {
+                let index = y as usize * size + x as usize;
+                if depth <= self.depths[index] + 0.05 {
+                    self.pixels[index] = color;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hash::FxHashSet;
+
+    use crate::generator::{generate_polycubes_single_threaded, get_known_one_sided_count};
+
+    fn csv_rows(polycubes: &[Polycube]) -> Vec<Vec<String>> {
+        let mut buffer = Vec::new();
+        write_csv_header(&mut buffer).unwrap();
+        for entry in &create_catalog(polycubes) {
+            write_csv_entry(&mut buffer, entry).unwrap();
+        }
+        String::from_utf8(buffer).unwrap().lines()
+            .map(|line| line.split(',').map(str::to_string).collect())
+            .collect()
+    }
+
+    // Checks the exported file against the schema documented on export_to_viewer_json:
+    // an array of objects with exactly an integer id, a type and a list of [x, y, z] cubes
+    #[test]
+    fn viewer_json_matches_schema() {
+        let n = 4;
+        let polycubes = generate_polycubes_single_threaded(n);
+        let path = std::env::temp_dir().join(format!("polycube_viewer_json_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        export_to_viewer_json(&polycubes, n, path, SortKey::Canonical, false).unwrap();
+        let contents = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        let entries = value.as_array().unwrap();
+        assert_eq!(entries.len(), polycubes.len());
+        for (i, entry) in entries.iter().enumerate() {
+            let object = entry.as_object().unwrap();
+            let mut fields: Vec<&str> = object.keys().map(String::as_str).collect();
+            fields.sort_unstable();
+            assert_eq!(fields, ["cubes", "id", "type"]);
+
+            assert_eq!(object["id"].as_u64(), Some(i as u64 + 1));
+            assert!(["Linear", "Flat", "3D"].contains(&object["type"].as_str().unwrap()));
+            let cubes = object["cubes"].as_array().unwrap();
+            assert_eq!(cubes.len(), n as usize);
+            for cube in cubes {
+                let coords = cube.as_array().unwrap();
+                assert_eq!(coords.len(), 3);
+                assert!(coords.iter().all(|coord| coord.is_i64()));
+            }
+        }
+    }
+
+    // The DOT file has one node per shape of each size and one edge into every node but
+    // the unit cube
+    #[test]
+    fn growth_tree_has_a_node_per_shape() {
+        let path = std::env::temp_dir().join(format!("polycube_growth_tree_test_{}.dot", std::process::id()));
+        let path = path.to_str().unwrap();
+        export_growth_tree(6, path).unwrap();
+        let contents = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        for n in 1..=6u8 {
+            let nodes = contents.lines().filter(|line| line.trim_start().starts_with(&format!("n{}_", n)) && line.contains("[label")).count();
+            let edges = contents.lines().filter(|line| line.contains(&format!("-> n{}_", n))).count();
+            assert_eq!(nodes as Count, get_known_one_sided_count(n).unwrap(), "nodes of size {}", n);
+            assert_eq!(edges, if n == 1 { 0 } else { nodes }, "edges into size {}", n);
+        }
+    }
+
+    // The version 1 columns keep their positions; newer columns come after CubeZ
+    #[test]
+    fn csv_header_appends_new_columns() {
+        let rows = csv_rows(&generate_polycubes_single_threaded(3));
+        let header = &rows[0];
+        assert_eq!(header[..10], ["ID", "ShapeType", "DimensionX", "DimensionY", "DimensionZ", "SurfaceArea", "Connectivity", "CubeX", "CubeY", "CubeZ"]);
+        assert_eq!(header[10..], ["BranchPoints", "FillsBox", "CanonicalKey", "SchemaVersion"]);
+
+        for row in &rows[1..] {
+            assert_eq!(row.len(), header.len());
+            assert_eq!(row[13], CSV_SCHEMA_VERSION.to_string());
+        }
+    }
+
+    // Every rotation of a shape writes the same CanonicalKey, which parses back into the
+    // shape; distinct shapes write distinct keys
+    #[test]
+    fn csv_canonical_keys_identify_shapes() {
+        let polycubes = generate_polycubes_single_threaded(5);
+        let key_of = |polycube: &Polycube| csv_rows(std::slice::from_ref(polycube))[1][12].clone();
+
+        let mut keys = FxHashSet::default();
+        for polycube in &polycubes {
+            let key = key_of(polycube);
+            for index in [1, 7, 13, 23] {
+                assert_eq!(key_of(&polycube.rotate(index)), key);
+            }
+            let parsed = Polycube::from_canonical_key(&key).unwrap();
+            assert_eq!(parsed.canonical_form(), polycube.canonical_form());
+            keys.insert(key);
+        }
+        assert_eq!(keys.len(), polycubes.len());
+    }
+
+    // Surface areas never decrease along the output, and shapes with equal areas keep
+    // their IDs in order
+    #[test]
+    fn surface_area_sort_is_non_decreasing() {
+        let ordered = order_polycubes(&create_catalog(&generate_polycubes_single_threaded(6)), SortKey::SurfaceArea);
+        assert_eq!(ordered.len(), 166);
+        for pair in ordered.windows(2) {
+            let (a, b) = (&pair[0].metrics, &pair[1].metrics);
+            assert!(a.surface_area <= b.surface_area);
+            if a.surface_area == b.surface_area {
+                assert!(pair[0].id < pair[1].id);
+            }
+        }
+    }
+
+    // IDs rank canonical forms, so known shapes keep their IDs whatever order or
+    // orientation they arrive in
+    #[test]
+    fn catalog_ids_are_pinned() {
+        let id_of = |polycubes: &[Polycube], cubes: &[(i8, i8, i8)]| {
+            let target = Polycube::new(cubes.iter().map(|&(x, y, z)| Pos::new(x, y, z)).collect()).canonical_form();
+            create_catalog(polycubes).iter().find(|entry| entry.polycube.canonical_form() == target).unwrap().id
+        };
+
+        let mut trominoes = generate_polycubes_single_threaded(3);
+        assert_eq!(id_of(&trominoes, &[(0, 0, 0), (1, 0, 0), (2, 0, 0)]), 1);
+        assert_eq!(id_of(&trominoes, &[(0, 0, 0), (1, 0, 0), (0, 1, 0)]), 2);
+        trominoes.reverse();
+        trominoes[0] = trominoes[0].rotate(9);
+        assert_eq!(id_of(&trominoes, &[(0, 0, 0), (1, 0, 0), (2, 0, 0)]), 1);
+
+        let tetracubes = generate_polycubes_single_threaded(4);
+        assert_eq!(id_of(&tetracubes, &[(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0)]), 1);
+        assert_eq!(id_of(&tetracubes, &[(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0)]), 4);
+    }
+
+    // One call writes one file per format, listing the same shapes with the same IDs,
+    // types and cubes in the same order
+    #[test]
+    fn export_all_writes_consistent_csv_and_json() {
+        let dir = std::env::temp_dir().join(format!("polycube_export_all_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let polycubes = generate_polycubes_single_threaded(4);
+        let report = export_all(&polycubes, 4, dir.to_str().unwrap(), &[ExportFormat::Csv, ExportFormat::Json], SortKey::SurfaceArea, false).unwrap();
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        let csv = std::fs::read_to_string(&report.paths[0]).unwrap();
+        let json = std::fs::read_to_string(&report.paths[1]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, 2);
+        assert_eq!(report.shapes_written, 8);
+        assert!(report.paths[0].ends_with("polycubes_4.csv") && report.paths[1].ends_with("polycubes_4.json"));
+
+        // Rebuild the JSON's (id, type, cubes) list from the CSV rows
+        let mut from_csv: Vec<(u64, String, Vec<i64>)> = Vec::new();
+        for line in csv.lines().skip(1) {
+            let fields: Vec<&str> = line.split(',').collect();
+            let id: u64 = fields[0].parse().unwrap();
+            if from_csv.last().is_none_or(|(last, _, _)| *last != id) {
+                from_csv.push((id, fields[1].to_string(), Vec::new()));
+            }
+            from_csv.last_mut().unwrap().2.extend(fields[7..10].iter().map(|c| c.parse::<i64>().unwrap()));
+        }
+
+        let entries: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let from_json: Vec<(u64, String, Vec<i64>)> = entries.as_array().unwrap().iter()
+            .map(|entry| (
+                entry["id"].as_u64().unwrap(),
+                entry["type"].as_str().unwrap().to_string(),
+                entry["cubes"].as_array().unwrap().iter().flat_map(|cube| cube.as_array().unwrap().iter().map(|c| c.as_i64().unwrap())).collect(),
+            ))
+            .collect();
+        assert_eq!(from_csv, from_json);
+    }
+
+    // The first tricube on its own: a CSV with one row per cube and a viewer JSON with
+    // one entry, both under ID 1
+    #[test]
+    fn export_single_writes_one_shape() {
+        let polycube = &generate_polycubes_single_threaded(3)[0];
+        let path = std::env::temp_dir().join(format!("polycube_export_single_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        export_single(polycube, ExportFormat::Csv, path).unwrap();
+        let csv = std::fs::read_to_string(path).unwrap();
+        export_single(polycube, ExportFormat::Json, path).unwrap();
+        let json = std::fs::read_to_string(path).unwrap();
+        export_single(polycube, ExportFormat::Text, path).unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[1..].iter().all(|row| row.starts_with("1,")));
+
+        let entries: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let entries = entries.as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["id"], 1);
+        assert_eq!(entries[0]["cubes"].as_array().unwrap().len(), 3);
+
+        assert!(!text.is_empty());
+    }
+
+    // The file decodes as a looping GIF with one full-size frame per requested step, and
+    // shapes or frame counts that cannot be animated are rejected
+    #[cfg(feature = "gif")]
+    #[test]
+    fn rotation_gif_has_the_requested_frames() {
+        let l_tricube = Polycube::new(vec![Pos::new(0, 0, 0), Pos::new(1, 0, 0), Pos::new(0, 1, 0)]);
+        let path = std::env::temp_dir().join(format!("polycube_rotation_gif_test_{}.gif", std::process::id()));
+        export_rotation_gif(&l_tricube, path.to_str().unwrap(), 6).unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (GIF_SIZE, GIF_SIZE));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height), (GIF_SIZE, GIF_SIZE));
+            assert_eq!(frame.delay, GIF_FRAME_DELAY);
+            frames += 1;
+        }
+        assert_eq!(frames, 6);
+        assert_eq!(decoder.repeat(), gif::Repeat::Infinite);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(export_rotation_gif(&l_tricube, path.to_str().unwrap(), 0).is_err());
+        assert!(export_rotation_gif(&Polycube::new(Vec::new()), path.to_str().unwrap(), 6).is_err());
+    }
+
+    // A hand-written file with comments, stray whitespace and negative coordinates reads
+    // back as the normalized shapes; the tetracubes written out the same way read back
+    // unchanged; and a disconnected shape is reported by its index
+    #[test]
+    fn coords_file_round_trips() {
+        let path = std::env::temp_dir().join(format!("polycube_coords_test_{}.txt", std::process::id()));
+        let path_str = path.to_str().unwrap();
+
+        std::fs::write(&path, "# an L-tricube\n -1  0 2\n0 0 2\n-1 1\t2\n\n\n# a domino\n5 -3 -4\n5 -3 -3\n").unwrap();
+        let shapes = import_from_coords(path_str).unwrap();
+        assert_eq!(shapes.len(), 2);
+        assert_eq!(shapes[0].sorted_cubes(), vec![Pos::new(0, 0, 0), Pos::new(0, 1, 0), Pos::new(1, 0, 0)]);
+        assert_eq!(shapes[1].sorted_cubes(), vec![Pos::new(0, 0, 0), Pos::new(0, 0, 1)]);
+
+        let tetracubes = generate_polycubes_single_threaded(4);
+        let text: Vec<String> = tetracubes.iter()
+            .map(|polycube| polycube.cubes.iter().map(|p| format!("{} {} {}\n", p.x, p.y, p.z)).collect())
+            .collect();
+        std::fs::write(&path, text.join("\n")).unwrap();
+        let shapes = import_from_coords(path_str).unwrap();
+        assert_eq!(shapes.len(), tetracubes.len());
+        for (read, written) in shapes.iter().zip(&tetracubes) {
+            assert_eq!(read.sorted_cubes(), written.normalize().sorted_cubes());
+        }
+
+        std::fs::write(&path, "0 0 0\n\n0 0 0\n2 0 0\n").unwrap();
+        let error = import_from_coords(path_str).unwrap_err();
+        assert!(error.to_string().contains("shape 1 is not connected"));
+
+        std::fs::write(&path, "0 0\n").unwrap();
+        assert!(import_from_coords(path_str).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // The parallel and single-threaded generators list shapes in different orders and
+    // orientations, and a shuffled, turned copy differs again, yet all number every shape
+    // alike
+    #[test]
+    fn catalog_ids_match_across_runs() {
+        let id_map = |polycubes: &[Polycube]| -> Vec<(usize, Vec<Pos>)> {
+            let mut ids: Vec<(usize, Vec<Pos>)> = create_catalog(polycubes).iter()
+                .map(|entry| (entry.id, entry.polycube.canonical_form()))
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        let single = generate_polycubes_single_threaded(5);
+        let parallel = crate::generator::generate_polycubes_with_policy(5, crate::generator::CachePolicy::None);
+        let mut shuffled: Vec<Polycube> = single.iter().enumerate()
+            .map(|(i, polycube)| polycube.rotate(i % 24))
+            .collect();
+        shuffled.reverse();
+        shuffled.swap(0, 11);
+
+        let expected = id_map(&single);
+        assert_eq!(id_map(&parallel), expected);
+        assert_eq!(id_map(&shuffled), expected);
+        assert_eq!(expected.iter().map(|(id, _)| *id).collect::<Vec<_>>(), (1..=single.len()).collect::<Vec<_>>());
+    }
+
+    // Streamed CSV and JSON lines, read back into shapes, hold exactly the shapes the full
+    // generator returns for every n up to 6; a text report cannot be streamed
+    #[test]
+    fn streamed_export_reimports_as_the_full_output() {
+        let config = GeneratorConfig { cache_policy: crate::generator::CachePolicy::None, ..GeneratorConfig::default() };
+        let path = std::env::temp_dir().join(format!("polycube_stream_export_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let forms = |polycubes: Vec<Vec<Pos>>| -> FxHashSet<Vec<Pos>> {
+            polycubes.into_iter().map(|cubes| Polycube::new(cubes).canonical_form()).collect()
+        };
+
+        for n in 1..=6 {
+            let expected: FxHashSet<Vec<Pos>> = generate_polycubes_single_threaded(n).iter().map(Polycube::canonical_form).collect();
+
+            assert_eq!(generate_and_export(n, ExportFormat::Csv, path, &config).unwrap(), expected.len() as u64);
+            let mut by_id: BTreeMap<u64, Vec<Pos>> = BTreeMap::new();
+            for line in std::fs::read_to_string(path).unwrap().lines().skip(1) {
+                let fields: Vec<&str> = line.split(',').collect();
+                let coord = |i: usize| fields[i].parse::<i8>().unwrap();
+                by_id.entry(fields[0].parse().unwrap()).or_default().push(Pos::new(coord(7), coord(8), coord(9)));
+            }
+            assert_eq!(by_id.len(), expected.len());
+            assert_eq!(forms(by_id.into_values().collect()), expected);
+
+            assert_eq!(generate_and_export(n, ExportFormat::Json, path, &config).unwrap(), expected.len() as u64);
+            let shapes: Vec<Vec<Pos>> = std::fs::read_to_string(path).unwrap().lines()
+                .map(|line| {
+                    let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+                    entry["cubes"].as_array().unwrap().iter()
+                        .map(|cube| {
+                            let c: Vec<i8> = cube.as_array().unwrap().iter().map(|c| c.as_i64().unwrap() as i8).collect();
+                            Pos::new(c[0], c[1], c[2])
+                        })
+                        .collect()
+                })
+                .collect();
+            assert_eq!(shapes.len(), expected.len());
+            assert_eq!(forms(shapes), expected);
+        }
+        std::fs::remove_file(path).unwrap();
+
+        assert!(generate_and_export(3, ExportFormat::Text, path, &config).is_err());
+    }
+}
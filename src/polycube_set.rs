@@ -0,0 +1,224 @@
+use std::mem::{size_of, size_of_val};
+
+use rustc_hash::FxHashMap;
+use serde::{Serialize, Deserialize};
+
+use crate::polycube::{Polycube, Pos};
+use crate::rotation::CanonicalKey;
+
+// Packed store for a large result set: the cubes of every shape in one flat Vec, with
+// shape i at cubes[offsets[i]..offsets[i + 1]]. A Vec<Polycube> pays a 24-byte Vec header
+// plus a separate heap allocation per shape; here each shape costs 4 bytes of offset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolycubeSet {
+    cubes: Vec<Pos>,
+    // One more entry than there are shapes; always starts with 0
+    offsets: Vec<u32>,
+}
+
+// Borrowed shape inside a PolycubeSet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PolycubeView<'a> {
+    pub cubes: &'a [Pos],
+}
+
+impl PolycubeView<'_> {
+    pub fn len(&self) -> usize {
+        self.cubes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cubes.is_empty()
+    }
+
+    // Copy into an owned Polycube, to use the analysis methods
+    pub fn to_polycube(&self) -> Polycube {
+        Polycube::new(self.cubes.to_vec())
+    }
+}
+
+impl PolycubeSet {
+    pub fn new() -> Self {
+        PolycubeSet { cubes: Vec::new(), offsets: vec![0] }
+    }
+
+    // Reserve room for `shapes` shapes of `size` cubes each
+    pub fn with_capacity(shapes: usize, size: usize) -> Self {
+        let mut offsets = Vec::with_capacity(shapes + 1);
+        offsets.push(0);
+        PolycubeSet { cubes: Vec::with_capacity(shapes * size), offsets }
+    }
+
+    // Append a shape. Panics if the set would hold more than u32::MAX cubes.
+    pub fn push(&mut self, cubes: &[Pos]) {
+        self.cubes.extend_from_slice(cubes);
+        let end = u32::try_from(self.cubes.len()).expect("PolycubeSet holds at most u32::MAX cubes");
+        self.offsets.push(end);
+    }
+
+    // Number of shapes
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<PolycubeView<'_>> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let start = self.offsets[index] as usize;
+        let end = self.offsets[index + 1] as usize;
+        Some(PolycubeView { cubes: &self.cubes[start..end] })
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = PolycubeView<'_>> + '_ {
+        self.offsets.windows(2).map(move |bounds| PolycubeView {
+            cubes: &self.cubes[bounds[0] as usize..bounds[1] as usize],
+        })
+    }
+
+    // Unpack into one Polycube per shape
+    pub fn to_polycubes(&self) -> Vec<Polycube> {
+        self.iter().map(|view| view.to_polycube()).collect()
+    }
+
+    // Bytes of heap memory held by the set
+    pub fn heap_bytes(&self) -> usize {
+        self.cubes.capacity() * size_of::<Pos>() + self.offsets.capacity() * size_of::<u32>()
+    }
+
+    // Bytes of heap memory a Vec<Polycube> holding the same shapes would use,
+    // not counting allocator overhead per shape. With inline-cubes the cubes of small
+    // shapes are part of size_of::<Polycube>() instead.
+    pub fn vec_heap_bytes(polycubes: &[Polycube]) -> usize {
+        size_of_val(polycubes) + polycubes.iter().map(Polycube::heap_bytes).sum::<usize>()
+    }
+}
+
+impl Default for PolycubeSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&[Polycube]> for PolycubeSet {
+    fn from(polycubes: &[Polycube]) -> Self {
+        let size = polycubes.first().map_or(0, |p| p.cubes.len());
+        let mut set = PolycubeSet::with_capacity(polycubes.len(), size);
+        for polycube in polycubes {
+            set.push(&polycube.cubes);
+        }
+        set
+    }
+}
+
+impl From<Vec<Polycube>> for PolycubeSet {
+    fn from(polycubes: Vec<Polycube>) -> Self {
+        PolycubeSet::from(polycubes.as_slice())
+    }
+}
+
+impl From<PolycubeSet> for Vec<Polycube> {
+    fn from(set: PolycubeSet) -> Self {
+        set.to_polycubes()
+    }
+}
+
+impl FromIterator<Polycube> for PolycubeSet {
+    fn from_iter<I: IntoIterator<Item = Polycube>>(iter: I) -> Self {
+        let mut set = PolycubeSet::new();
+        for polycube in iter {
+            set.push(&polycube.cubes);
+        }
+        set
+    }
+}
+
+// Lookup from any orientation of a shape to its position in a set of shapes, e.g. the
+// output of generate_polycubes. Keyed by the full canonical_key rather than a hash, so
+// distinct shapes can never collide. For a set in export order, index + 1 is the CSV ID.
+#[derive(Debug, Clone, Default)]
+pub struct PolycubeIndex {
+    indices: FxHashMap<CanonicalKey, usize>,
+    // Cube count of the indexed shapes, if they all have the same size
+    size: Option<usize>,
+}
+
+impl PolycubeIndex {
+    // Index the shapes; if a shape appears more than once, the first position is kept
+    pub fn new(polycubes: &[Polycube]) -> Self {
+        let mut indices = FxHashMap::default();
+        for (index, polycube) in polycubes.iter().enumerate() {
+            indices.entry(polycube.canonical_key()).or_insert(index);
+        }
+        
+        let size = polycubes.first().map(|p| p.cubes.len())
+            .filter(|&size| polycubes.iter().all(|p| p.cubes.len() == size));
+        PolycubeIndex { indices, size }
+    }
+
+    // Position of the shape in the indexed set, in any rotation or translation; None for
+    // shapes not in the set, including those of a different size
+    pub fn lookup(&self, polycube: &Polycube) -> Option<usize> {
+        // Skip computing the key when the size alone rules the shape out
+        if self.size.is_some_and(|size| size != polycube.cubes.len()) {
+            return None;
+        }
+        
+        self.indices.get(&polycube.canonical_key()).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_polycubes_single_threaded;
+
+    // Packing and unpacking keeps every shape and its cube order, and the packed set
+    // takes less heap than the Vec it came from
+    #[test]
+    fn packed_set_round_trips() {
+        let polycubes = generate_polycubes_single_threaded(6);
+        let set = PolycubeSet::from(polycubes.as_slice());
+        assert_eq!(set.len(), polycubes.len());
+        assert_eq!(set.get(3).unwrap().cubes, polycubes[3].cubes.as_slice());
+        assert!(set.get(polycubes.len()).is_none());
+        assert!(set.iter().all(|view| view.len() == 6));
+
+        let unpacked: Vec<Polycube> = set.clone().into();
+        assert_eq!(unpacked, polycubes);
+        assert_eq!(polycubes.iter().cloned().collect::<PolycubeSet>(), set);
+        assert!(set.heap_bytes() < PolycubeSet::vec_heap_bytes(&polycubes));
+
+        let empty = PolycubeSet::new();
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().count(), 0);
+    }
+
+    // Every shape, in any orientation, looks up to its own position; other sizes do not
+    #[test]
+    fn index_finds_each_shape_at_its_position() {
+        let polycubes = generate_polycubes_single_threaded(5);
+        let index = PolycubeIndex::new(&polycubes);
+        assert_eq!(index.len(), polycubes.len());
+        for (position, polycube) in polycubes.iter().enumerate() {
+            assert_eq!(index.lookup(polycube), Some(position));
+            assert_eq!(index.lookup(&polycube.rotate(21).translate(Pos::new(2, 2, -1))), Some(position));
+        }
+
+        assert_eq!(index.lookup(&generate_polycubes_single_threaded(4)[0]), None);
+        assert!(PolycubeIndex::new(&[]).is_empty());
+    }
+}
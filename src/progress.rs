@@ -0,0 +1,101 @@
+// Progress reporting shared by the generator and the counter. With the `indicatif` feature
+// progress is drawn as a bar; without it the plain percentage lines are printed.
+
+#[cfg(feature = "indicatif")]
+use indicatif::{ProgressBar, ProgressStyle};
+#[cfg(not(feature = "indicatif"))]
+use std::io::Write;
+#[cfg(not(feature = "indicatif"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// A bar over `total` tasks of which `done` are already finished, or a hidden one when
+// progress is not shown. ProgressBar is thread-safe, so workers can call inc directly.
+#[cfg(feature = "indicatif")]
+pub(crate) fn task_bar(total: usize, done: usize, message: String, visible: bool) -> ProgressBar {
+    if !visible {
+        return ProgressBar::hidden();
+    }
+
+    let bar = ProgressBar::new(total as u64);
+    let style = ProgressStyle::with_template("{msg} [{bar:40}] {pos}/{len} ({percent}%) {elapsed_precise}, eta {eta}")
+        .expect("progress template is valid")
+        .progress_chars("=> ");
+    bar.set_style(style);
+    bar.set_message(message);
+    bar.set_position(done as u64);
+    bar
+}
+
+// Progress through the base polycubes while generating size n
+pub(crate) struct LayerProgress {
+    #[cfg(feature = "indicatif")]
+    bar: ProgressBar,
+    #[cfg(not(feature = "indicatif"))]
+    done: AtomicUsize,
+    #[cfg(not(feature = "indicatif"))]
+    total: usize,
+    #[cfg(not(feature = "indicatif"))]
+    n: usize,
+}
+
+impl LayerProgress {
+    pub(crate) fn new(n: usize, done: usize, total: usize) -> Self {
+        #[cfg(feature = "indicatif")]
+        return LayerProgress { bar: task_bar(total, done, format!("Generating polycubes n={}", n), true) };
+
+        #[cfg(not(feature = "indicatif"))]
+        LayerProgress { done: AtomicUsize::new(done), total, n }
+    }
+
+    // Record one finished base polycube; safe to call from several threads
+    pub(crate) fn inc(&self) {
+        #[cfg(feature = "indicatif")]
+        self.bar.inc(1);
+
+        // Print the percentage periodically
+        #[cfg(not(feature = "indicatif"))]
+        {
+            let idx = self.done.fetch_add(1, Ordering::SeqCst);
+            if idx.is_multiple_of(100) || idx == self.total - 1 {
+                print!("\rGenerating polycubes n={}: {:.1}%", self.n, (idx as f32 / self.total as f32) * 100.0);
+                let _ = std::io::stdout().flush();
+            }
+        }
+    }
+
+    pub(crate) fn finish(&self) {
+        #[cfg(feature = "indicatif")]
+        self.bar.finish();
+
+        #[cfg(not(feature = "indicatif"))]
+        println!("\rGenerating polycubes n={}: 100%", self.n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Increments from several threads all land, on top of the work already done
+    #[test]
+    fn parallel_increments_are_all_counted() {
+        let progress = LayerProgress::new(5, 10, 410);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| (0..100).for_each(|_| progress.inc()));
+            }
+        });
+
+        #[cfg(feature = "indicatif")]
+        assert_eq!(progress.bar.position(), 410);
+        #[cfg(not(feature = "indicatif"))]
+        assert_eq!(progress.done.load(Ordering::SeqCst), 410);
+    }
+
+    #[cfg(feature = "indicatif")]
+    #[test]
+    fn hidden_bar_when_progress_is_off() {
+        assert!(task_bar(10, 0, "Counting".to_string(), false).is_hidden());
+        assert_eq!(task_bar(10, 4, "Counting".to_string(), true).position(), 4);
+    }
+}
@@ -1,7 +1,11 @@
-use crate::polycube::{Polycube, Pos};
-use rustc_hash::FxHasher;
+use crate::polycube::{Connectivity, Polycube, Pos, ShapeSignature};
+use crate::safe_counter::BBOX_SPAN;
+use rustc_hash::{FxHashSet, FxHasher};
 use std::hash::{Hash, Hasher};
 
+// Rotation-invariant string key of a shape, as produced by Polycube::canonical_key
+pub type CanonicalKey = String;
+
 // Apply a rotation matrix to this polycube
 impl Polycube {
     #[inline]
@@ -16,26 +20,16 @@ impl Polycube {
         Self::new(new_cubes)
     }
 
-    // Get canonical form hash for uniqueness testing
-    // Returns a 64-bit hash of the canonicalized polycube
-    pub fn get_canonical_hash(&self) -> u64 {
-        let rotations = all_rotations(self);
+    // Get the canonical form: the lexicographically smallest sorted rotation
+    pub fn canonical_form(&self) -> Vec<Pos> {
+        let rotations = canonical_candidates(self);
         
         // Find the lexicographically smallest rotation
         let mut smallest: Option<Vec<Pos>> = None;
         
         for rotation in &rotations {
             // Sort positions for consistent ordering
-            let mut positions: Vec<_> = rotation.cubes.clone();
-            positions.sort_by(|a, b| {
-                match a.x.cmp(&b.x) {
-                    std::cmp::Ordering::Equal => match a.y.cmp(&b.y) {
-                        std::cmp::Ordering::Equal => a.z.cmp(&b.z),
-                        other => other,
-                    },
-                    other => other,
-                }
-            });
+            let positions = rotation.sorted_cubes();
             
             // If this is the first rotation or it's smaller than the current smallest
             if smallest.is_none() || lexicographically_smaller(&positions, smallest.as_ref().unwrap()) {
@@ -43,8 +37,206 @@ impl Polycube {
             }
         }
         
+        smallest.unwrap_or_default()
+    }
+
+    // Get the free parent: among the connected shapes left by removing a single cube,
+    // the one with the smallest canonical form. Every rotation of a shape has the same
+    // parent, returned in its canonical orientation. A single cube has no parent.
+    pub fn free_parent(&self) -> Option<Polycube> {
+        if self.cubes.len() <= 1 {
+            return None;
+        }
+        
+        self.removable_cubes()
+            .into_iter()
+            .map(|pos| self.without(pos).canonical_form())
+            .min()
+            .map(Polycube::new)
+    }
+
+    // Every distinct (up to rotation) shape made by adding one face-adjacent cube, each
+    // normalized, in order of the added position. Duplicates are only removed among this
+    // shape's own children, so two shapes can have a child in common.
+    pub fn children(&self) -> Vec<Polycube> {
+        let mut positions: Vec<Pos> = self.get_expansion_positions(Connectivity::Face).into_iter().collect();
+        positions.sort();
+        
+        let mut seen = FxHashSet::default();
+        positions.into_iter()
+            .map(|pos| self.expand_unchecked(pos).normalize())
+            .filter(|child| seen.insert(child.canonical_form()))
+            .collect()
+    }
+
+    // Every distinct placement of the shape inside a bx × by × bz box with a corner at the
+    // origin: each distinct orientation at each translation that keeps it in the box.
+    // Symmetric shapes have fewer distinct orientations, so fewer placements; a single
+    // cube has one per cell. Cubes of each placement are sorted.
+    pub fn placements_in_box(&self, bx: i8, by: i8, bz: i8) -> Vec<Polycube> {
+        if self.cubes.is_empty() {
+            return Vec::new();
+        }
+        
+        let mut seen = FxHashSet::default();
+        let orientations: Vec<Polycube> = all_rotations(self).into_iter()
+            .map(|rotated| Polycube::new(rotated.sorted_cubes()))
+            .filter(|rotated| seen.insert(rotated.cubes.clone()))
+            .collect();
+        
+        let mut placements = Vec::new();
+        for orientation in &orientations {
+            let (width, height, depth) = orientation.get_dimensions();
+            for x in 0..=bx - width {
+                for y in 0..=by - height {
+                    for z in 0..=bz - depth {
+                        placements.push(orientation.translate(Pos::new(x, y, z)));
+                    }
+                }
+            }
+        }
+        placements
+    }
+
+    // Distance between two same-size shapes: the fewest cubes of `other` that must move
+    // to turn it into `self`, minimized over all rotations and translations of `other`.
+    // Equals half the symmetric difference at the best alignment, so rotations are 0 apart.
+    pub fn shape_distance(&self, other: &Polycube) -> usize {
+        let occupied = self.occupancy_set();
+        let mut best_overlap = 0;
+        
+        for rotated in all_rotations(other) {
+            // Any alignment with overlap maps some cube of other onto some cube of self
+            for &anchor in &self.cubes {
+                for &cube in &rotated.cubes {
+                    let offset = anchor - cube;
+                    let overlap = rotated.cubes.iter()
+                        .filter(|&&p| occupied.contains(&(p + offset)))
+                        .count();
+                    best_overlap = best_overlap.max(overlap);
+                }
+            }
+        }
+        
+        self.cubes.len().max(other.cubes.len()) - best_overlap
+    }
+
+    // Check whether some rotation and translation of `other` fits entirely inside this shape
+    pub fn contains_subshape(&self, other: &Polycube) -> bool {
+        if other.cubes.is_empty() {
+            return true;
+        }
+        
+        let occupied = self.occupancy_set();
+        
+        all_rotations(other).iter().any(|rotated| {
+            // Try mapping the first cube of the rotated shape onto every cube of self
+            self.cubes.iter().any(|&anchor| {
+                let offset = anchor - rotated.cubes[0];
+                rotated.cubes.iter().all(|&p| occupied.contains(&(p + offset)))
+            })
+        })
+    }
+
+    // Indices (into generate_rotation_matrices) of the rotations that map the shape onto
+    // itself up to translation: its stabilizer subgroup. Always starts with 0, the identity;
+    // a single cube gives all 24 and an asymmetric shape only [0].
+    pub fn automorphisms(&self) -> Vec<usize> {
+        let original = self.normalize().sorted_cubes();
+        
+        all_rotations(self).iter()
+            .enumerate()
+            .filter(|(_, rotated)| rotated.sorted_cubes() == original)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // Number of rotations that leave the shape unchanged (up to translation)
+    pub fn symmetry_order(&self) -> usize {
+        self.automorphisms().len()
+    }
+
+    // Rotation-invariant summary of size, extents, surface area and symmetry order
+    pub fn signature(&self) -> ShapeSignature {
+        let (x, y, z) = self.normalize().get_dimensions();
+        let mut dimensions = [x, y, z];
+        dimensions.sort();
+        
+        ShapeSignature {
+            size: self.cubes.len(),
+            dimensions,
+            surface_area: self.surface_area(),
+            symmetry_order: self.symmetry_order(),
+        }
+    }
+
+    // Rotate by the matrix at `index` in generate_rotation_matrices, then normalize.
+    // Panics if index >= ROTATION_COUNT.
+    pub fn rotate(&self, index: usize) -> Self {
+        self.apply_rotation(&generate_rotation_matrices()[index]).normalize()
+    }
+
+    // Index of a rotation that maps this shape onto `other` up to translation, i.e. the
+    // smallest i with self.rotate(i) equal to other.normalize(); None if `other` is not a
+    // rotation of this shape. Symmetric shapes match several indices.
+    pub fn rotation_mapping(&self, other: &Polycube) -> Option<usize> {
+        if self.cubes.len() != other.cubes.len() {
+            return None;
+        }
+        
+        let target = other.normalize().sorted_cubes();
+        all_rotations(self).iter().position(|rotated| rotated.sorted_cubes() == target)
+    }
+
+    // Canonical form as a compact string, identical for all rotations of a shape and
+    // distinct for different shapes: three base-36 digits (x, y, z) per cube. Suitable as a
+    // join key across runs; from_canonical_key turns it back into the shape.
+    // Panics if the shape spans more than 36 cells along an axis (a straight line of 37 or
+    // more cubes), since a coordinate of 36 has no base-36 digit.
+    pub fn canonical_key(&self) -> CanonicalKey {
+        self.canonical_form().iter()
+            .flat_map(|pos| [pos.x, pos.y, pos.z])
+            .map(|coordinate| std::char::from_digit(coordinate as u32, 36).expect("canonical_key needs shapes at most 36 cells long"))
+            .collect()
+    }
+
+    // Parse a canonical_key back into its shape, in canonical orientation
+    pub fn from_canonical_key(key: &str) -> Option<Polycube> {
+        let digits = key.chars()
+            .map(|c| c.to_digit(36).map(|digit| digit as i8))
+            .collect::<Option<Vec<i8>>>()?;
+        if digits.len() % 3 != 0 {
+            return None;
+        }
+        
+        Some(Polycube::new(digits.chunks(3).map(|c| Pos::new(c[0], c[1], c[2])).collect()))
+    }
+
+    // Check whether `other` is a rotation of this shape, up to translation. Shapes that fit
+    // the 5x5x5 box of encode_bbox_u128 are compared as bitmasks, rotating only this shape
+    // and stopping at the first match, with no allocation or sorting; larger shapes use
+    // rotation_mapping.
+    pub fn is_rotation_of(&self, other: &Polycube) -> bool {
+        if self.cubes.len() != other.cubes.len() {
+            return false;
+        }
+        
+        let rotation_matrices = generate_rotation_matrices();
+        let Some(target) = rotated_bits(&other.cubes, &rotation_matrices[0]) else {
+            // Rotations keep the bounding box extents, so a rotation of this shape fits
+            // exactly when this shape does
+            return rotated_bits(&self.cubes, &rotation_matrices[0]).is_none()
+                && self.rotation_mapping(other).is_some();
+        };
+        
+        rotation_matrices.iter().any(|rotation| rotated_bits(&self.cubes, rotation) == Some(target))
+    }
+
+    // Get canonical form hash for uniqueness testing
+    // Returns a 64-bit hash of the canonicalized polycube
+    pub fn get_canonical_hash(&self) -> u64 {
         // Compute a 64-bit hash of the canonical form
-        let canonical_positions = smallest.unwrap();
+        let canonical_positions = self.canonical_form();
         let mut hasher = FxHasher::default();
         canonical_positions.hash(&mut hasher);
         hasher.finish()
@@ -83,7 +275,73 @@ fn lexicographically_smaller(a: &[Pos], b: &[Pos]) -> bool {
     a.len() < b.len()
 }
 
-// Generate all 24 rotations of a polycube
+// encode_bbox_u128 bitmask of the cubes after a rotation, translated to the origin, or None
+// if the shape does not fit the box. Works on the coordinates without building a Polycube.
+fn rotated_bits(cubes: &[Pos], rotation: &[[i8; 3]; 3]) -> Option<u128> {
+    let span = BBOX_SPAN as i32;
+    let rotate = |p: &Pos| -> [i32; 3] {
+        rotation.map(|row| row[0] as i32 * p.x as i32 + row[1] as i32 * p.y as i32 + row[2] as i32 * p.z as i32)
+    };
+    
+    let mut min = [i32::MAX; 3];
+    for cube in cubes {
+        let rotated = rotate(cube);
+        for axis in 0..3 {
+            min[axis] = min[axis].min(rotated[axis]);
+        }
+    }
+    
+    let mut bits = 0u128;
+    for cube in cubes {
+        let [x, y, z] = rotate(cube);
+        let (x, y, z) = (x - min[0], y - min[1], z - min[2]);
+        if x >= span || y >= span || z >= span {
+            return None;
+        }
+        bits |= 1u128 << (x * span * span + y * span + z);
+    }
+    
+    Some(bits)
+}
+
+// Orientations searched for the canonical form. A 3D shape needs all 24 rotations. A flat
+// shape is first turned to lie in the yz plane (x = 0); the rotations that keep it there
+// are the 8 that map the x axis onto itself, the symmetry group of a square, and they
+// reach every orientation the full group reaches in that plane. Any orientation outside
+// the plane can be turned into it by a quarter turn that moves its x coordinates into y or
+// z, which never makes the sorted list larger, so both searches give the same canonical form.
+fn canonical_candidates(polycube: &Polycube) -> Vec<Polycube> {
+    let normalized = polycube.normalize();
+    let (width, height, depth) = normalized.get_dimensions();
+    
+    if width != 1 && height != 1 && depth != 1 {
+        return all_rotations(&normalized);
+    }
+    
+    let rotation_matrices = generate_rotation_matrices();
+    
+    // Turn the thin axis onto x
+    let planar = if width == 1 {
+        normalized
+    } else if height == 1 {
+        normalized.apply_rotation(&rotation_matrices[Y_TO_X_ROTATION])
+    } else {
+        normalized.apply_rotation(&rotation_matrices[Z_TO_X_ROTATION])
+    };
+    
+    rotation_matrices[..PLANAR_ROTATIONS].iter()
+        .map(|rotation| planar.apply_rotation(rotation).normalize())
+        .collect()
+}
+
+// Indices into generate_rotation_matrices: the first 8 (+X and -X groups) keep the x axis
+// on itself, and these two rotate the y and z axes onto x
+const PLANAR_ROTATIONS: usize = 8;
+const Y_TO_X_ROTATION: usize = 8;
+const Z_TO_X_ROTATION: usize = 16;
+
+// Generate all 24 rotations of a polycube, normalized, in the order of
+// generate_rotation_matrices: all_rotations(p)[i] == p.rotate(i)
 pub fn all_rotations(polycube: &Polycube) -> Vec<Polycube> {
     let rotation_matrices = generate_rotation_matrices();
     let mut rotations = Vec::with_capacity(24);
@@ -96,45 +354,307 @@ pub fn all_rotations(polycube: &Polycube) -> Vec<Polycube> {
     rotations
 }
 
-// Generate all 24 rotation matrices
+// Number of orientation indices accepted by Polycube::rotate
+pub const ROTATION_COUNT: usize = 24;
+
+// Generate all 24 rotation matrices.
+//
+// The order is stable and may be relied on, e.g. to store orientation indices in files.
+// Matrices come in six groups of four, by which original axis becomes the new x axis:
+// 0-3 +x, 4-7 -x, 8-11 +y, 12-15 -y, 16-19 +z, 20-23 -z. Index 0 is the identity, and
+// indices 1-3 are quarter, half and three-quarter turns about the x axis. The first eight
+// are the rotations that keep the x axis on itself.
 pub fn generate_rotation_matrices() -> Vec<[[i8; 3]; 3]> {
-    let mut matrices = Vec::with_capacity(24);
-    
-    // +X face rotations
-    matrices.push([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
-    matrices.push([[1, 0, 0], [0, 0, -1], [0, 1, 0]]);
-    matrices.push([[1, 0, 0], [0, -1, 0], [0, 0, -1]]);
-    matrices.push([[1, 0, 0], [0, 0, 1], [0, -1, 0]]);
-    
-    // -X face rotations
-    matrices.push([[-1, 0, 0], [0, 1, 0], [0, 0, -1]]);
-    matrices.push([[-1, 0, 0], [0, 0, 1], [0, 1, 0]]);
-    matrices.push([[-1, 0, 0], [0, -1, 0], [0, 0, 1]]);
-    matrices.push([[-1, 0, 0], [0, 0, -1], [0, -1, 0]]);
-    
-    // +Y face rotations
-    matrices.push([[0, 1, 0], [-1, 0, 0], [0, 0, 1]]);
-    matrices.push([[0, 1, 0], [0, 0, -1], [-1, 0, 0]]);
-    matrices.push([[0, 1, 0], [1, 0, 0], [0, 0, -1]]);
-    matrices.push([[0, 1, 0], [0, 0, 1], [1, 0, 0]]);
-    
-    // -Y face rotations
-    matrices.push([[0, -1, 0], [1, 0, 0], [0, 0, 1]]);
-    matrices.push([[0, -1, 0], [0, 0, -1], [1, 0, 0]]);
-    matrices.push([[0, -1, 0], [-1, 0, 0], [0, 0, -1]]);
-    matrices.push([[0, -1, 0], [0, 0, 1], [-1, 0, 0]]);
-    
-    // +Z face rotations
-    matrices.push([[0, 0, 1], [0, 1, 0], [-1, 0, 0]]);
-    matrices.push([[0, 0, 1], [1, 0, 0], [0, 1, 0]]);
-    matrices.push([[0, 0, 1], [0, -1, 0], [1, 0, 0]]);
-    matrices.push([[0, 0, 1], [-1, 0, 0], [0, -1, 0]]);
-    
-    // -Z face rotations
-    matrices.push([[0, 0, -1], [0, 1, 0], [1, 0, 0]]);
-    matrices.push([[0, 0, -1], [-1, 0, 0], [0, 1, 0]]);
-    matrices.push([[0, 0, -1], [0, -1, 0], [-1, 0, 0]]);
-    matrices.push([[0, 0, -1], [1, 0, 0], [0, -1, 0]]);
-    
-    matrices
-}
\ No newline at end of file
+    vec![
+        // +X face rotations
+        [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        [[1, 0, 0], [0, 0, -1], [0, 1, 0]],
+        [[1, 0, 0], [0, -1, 0], [0, 0, -1]],
+        [[1, 0, 0], [0, 0, 1], [0, -1, 0]],
+        
+        // -X face rotations
+        [[-1, 0, 0], [0, 1, 0], [0, 0, -1]],
+        [[-1, 0, 0], [0, 0, 1], [0, 1, 0]],
+        [[-1, 0, 0], [0, -1, 0], [0, 0, 1]],
+        [[-1, 0, 0], [0, 0, -1], [0, -1, 0]],
+        
+        // +Y face rotations
+        [[0, 1, 0], [-1, 0, 0], [0, 0, 1]],
+        [[0, 1, 0], [0, 0, -1], [-1, 0, 0]],
+        [[0, 1, 0], [1, 0, 0], [0, 0, -1]],
+        [[0, 1, 0], [0, 0, 1], [1, 0, 0]],
+        
+        // -Y face rotations
+        [[0, -1, 0], [1, 0, 0], [0, 0, 1]],
+        [[0, -1, 0], [0, 0, -1], [1, 0, 0]],
+        [[0, -1, 0], [-1, 0, 0], [0, 0, -1]],
+        [[0, -1, 0], [0, 0, 1], [-1, 0, 0]],
+        
+        // +Z face rotations
+        [[0, 0, 1], [0, 1, 0], [-1, 0, 0]],
+        [[0, 0, 1], [1, 0, 0], [0, 1, 0]],
+        [[0, 0, 1], [0, -1, 0], [1, 0, 0]],
+        [[0, 0, 1], [-1, 0, 0], [0, -1, 0]],
+        
+        // -Z face rotations
+        [[0, 0, -1], [0, 1, 0], [1, 0, 0]],
+        [[0, 0, -1], [-1, 0, 0], [0, 1, 0]],
+        [[0, 0, -1], [0, -1, 0], [-1, 0, 0]],
+        [[0, 0, -1], [1, 0, 0], [0, -1, 0]],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::get_known_one_sided_count;
+
+    fn shape(cubes: &[(i8, i8, i8)]) -> Polycube {
+        Polycube::new(cubes.iter().map(|&(x, y, z)| Pos::new(x, y, z)).collect())
+    }
+
+    // A shape is 0 from itself and from any rotated, moved copy; an I- and an L-tetracube
+    // differ by one cube
+    #[test]
+    fn shape_distance_ignores_rotation_and_translation() {
+        let l_shape = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (0, 1, 0)]);
+        assert_eq!(l_shape.shape_distance(&l_shape), 0);
+        for index in 0..24 {
+            let moved = l_shape.rotate(index).translate(Pos::new(3, -2, 5));
+            assert_eq!(l_shape.shape_distance(&moved), 0);
+        }
+
+        let line = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0)]);
+        assert_eq!(l_shape.shape_distance(&line), 1);
+        assert_eq!(line.shape_distance(&l_shape), 1);
+    }
+
+    // Following free parents from any shape loses one cube per step and ends at the unit cube
+    #[test]
+    fn free_parent_chains_end_at_unit_cube() {
+        for polycube in crate::generator::generate_polycubes_single_threaded(6) {
+            let mut current = polycube.rotate(5);
+            while let Some(parent) = current.free_parent() {
+                assert_eq!(parent.cubes.len(), current.cubes.len() - 1);
+                assert!(parent.is_connected(Connectivity::Face));
+                current = parent;
+            }
+            assert_eq!(current.canonical_form(), Polycube::unit_cube().canonical_form());
+        }
+        assert_eq!(Polycube::unit_cube().free_parent(), None);
+    }
+
+    // Growing each shape only into the children whose free parent it is builds a tree with
+    // every shape of each size exactly once
+    #[test]
+    fn growth_tree_leaves_match_counts() {
+        let mut layer = vec![Polycube::unit_cube()];
+        for n in 2..=7u8 {
+            layer = layer.iter()
+                .flat_map(|parent| {
+                    let form = parent.canonical_form();
+                    parent.children().into_iter()
+                        .filter(move |child| child.free_parent().is_some_and(|p| p.cubes.as_slice() == form.as_slice()))
+                })
+                .collect();
+            assert_eq!(layer.len() as u128, get_known_one_sided_count(n).unwrap(), "tree nodes at n={}", n);
+        }
+    }
+
+    #[test]
+    fn rotations_share_a_signature() {
+        let skew = shape(&[(0, 0, 0), (1, 0, 0), (1, 1, 0), (1, 1, 1)]);
+        let signature = skew.signature();
+        assert_eq!(signature, ShapeSignature { size: 4, dimensions: [2, 2, 2], surface_area: 18, symmetry_order: 2 });
+        for index in 0..24 {
+            assert_eq!(skew.rotate(index).translate(Pos::new(3, -1, 2)).signature(), signature, "rotation {}", index);
+        }
+    }
+
+    // sorted_cubes orders cubes the way the canonical form comparison does, so the
+    // canonical form is the smallest sorted rotation under plain slice ordering
+    #[test]
+    fn sorted_cubes_uses_the_canonical_order() {
+        let polycube = shape(&[(2, 0, 1), (0, 1, 0), (1, 0, 0), (0, 0, 0), (1, 0, 1), (0, 1, 1)]);
+        let sorted = polycube.sorted_cubes();
+        assert_eq!(Polycube::new(sorted.clone()).sorted_cubes(), sorted);
+        for pair in sorted.windows(2) {
+            assert!(lexicographically_smaller(&pair[..1], &pair[1..]));
+            assert!(!lexicographically_smaller(&pair[1..], &pair[..1]));
+        }
+
+        let smallest = (0..24).map(|index| polycube.rotate(index).sorted_cubes()).min().unwrap();
+        assert_eq!(polycube.canonical_form(), smallest);
+    }
+
+    // The 8 planar candidates of a flat shape give the canonical form of the full group,
+    // whichever plane the shape lies in
+    #[test]
+    fn flat_canonical_forms_match_the_full_group() {
+        let flat = crate::generator::generate_polycubes_single_threaded(6).into_iter().filter(Polycube::is_flat);
+        for polycube in flat {
+            for index in [0, 5, 9, 17] {
+                let rotated = polycube.rotate(index);
+                assert_eq!(canonical_candidates(&rotated).len(), PLANAR_ROTATIONS);
+                let smallest = all_rotations(&rotated).iter().map(Polycube::sorted_cubes).min().unwrap();
+                assert_eq!(rotated.canonical_form(), smallest);
+            }
+        }
+    }
+
+    // Where each matrix sends the unit x, y and z vectors, before normalizing
+    fn images_of_axes(index: usize) -> [Pos; 3] {
+        let axes = Polycube::new(vec![Pos::new(1, 0, 0), Pos::new(0, 1, 0), Pos::new(0, 0, 1)]);
+        let rotated = axes.apply_rotation(&generate_rotation_matrices()[index]);
+        [rotated.cubes[0], rotated.cubes[1], rotated.cubes[2]]
+    }
+
+    // Pins the documented order: identity first, a quarter turn about x next, and groups
+    // of four by which axis turns onto x
+    #[test]
+    fn rotation_indices_are_stable() {
+        assert_eq!(images_of_axes(0), [Pos::new(1, 0, 0), Pos::new(0, 1, 0), Pos::new(0, 0, 1)]);
+        assert_eq!(images_of_axes(1), [Pos::new(1, 0, 0), Pos::new(0, 0, 1), Pos::new(0, -1, 0)]);
+        assert_eq!(images_of_axes(2), [Pos::new(1, 0, 0), Pos::new(0, -1, 0), Pos::new(0, 0, -1)]);
+        assert_eq!(images_of_axes(Y_TO_X_ROTATION), [Pos::new(0, -1, 0), Pos::new(1, 0, 0), Pos::new(0, 0, 1)]);
+        assert_eq!(images_of_axes(Z_TO_X_ROTATION), [Pos::new(0, 0, -1), Pos::new(0, 1, 0), Pos::new(1, 0, 0)]);
+
+        // The first row picks the original axis that becomes x
+        let onto_x = [[1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+        for (index, matrix) in generate_rotation_matrices().iter().enumerate() {
+            assert_eq!(matrix[0], onto_x[index / 4], "rotation {}", index);
+        }
+
+        let l_shape = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (0, 1, 0)]);
+        assert_eq!(l_shape.rotate(0).sorted_cubes(), l_shape.sorted_cubes());
+        assert_eq!(l_shape.rotate(1).sorted_cubes(), [Pos::new(0, 0, 0), Pos::new(0, 0, 1), Pos::new(1, 0, 0), Pos::new(2, 0, 0)]);
+    }
+
+    // An asymmetric shape recovers the exact index; a symmetric one gets the smallest
+    // index giving the same shape
+    #[test]
+    fn rotation_mapping_recovers_rotate() {
+        let chiral = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (2, 1, 0), (2, 1, 1)]);
+        assert_eq!(chiral.symmetry_order(), 1);
+        assert_eq!(chiral.rotation_mapping(&chiral.rotate(5)), Some(5));
+        for index in 0..ROTATION_COUNT {
+            assert_eq!(chiral.rotation_mapping(&chiral.rotate(index).translate(Pos::new(4, -2, 7))), Some(index));
+        }
+
+        let l_tromino = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        let mapped = l_tromino.rotation_mapping(&l_tromino.rotate(5)).unwrap();
+        assert!(mapped <= 5);
+        assert_eq!(l_tromino.rotate(mapped), l_tromino.rotate(5));
+
+        let mirrored = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (2, 1, 0), (2, 1, -1)]);
+        assert_eq!(chiral.rotation_mapping(&mirrored), None);
+        assert_eq!(chiral.rotation_mapping(&l_tromino), None);
+    }
+
+    // A 2×2 square has a 4-fold axis through its face: the turns about x keep it, and so
+    // do the half turns that flip it over
+    #[test]
+    fn square_automorphisms_include_its_four_fold_axis() {
+        let square = shape(&[(0, 0, 0), (0, 1, 0), (0, 0, 1), (0, 1, 1)]);
+        let automorphisms = square.automorphisms();
+        assert_eq!(automorphisms, [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert!(automorphisms.iter().all(|&index| square.rotate(index).sorted_cubes() == square.sorted_cubes()));
+
+        assert_eq!(shape(&[(0, 0, 0)]).automorphisms(), (0..ROTATION_COUNT).collect::<Vec<_>>());
+        let chiral = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (2, 1, 0), (2, 1, 1)]);
+        assert_eq!(chiral.automorphisms(), [0]);
+    }
+
+    // Keys parse back into the canonical orientation, up to the longest shape a key can
+    // describe
+    #[test]
+    fn canonical_key_round_trips() {
+        for polycube in crate::generator::generate_polycubes_single_threaded(5) {
+            let key = polycube.rotate(14).canonical_key();
+            assert_eq!(key.len(), 15);
+            let parsed = Polycube::from_canonical_key(&key).unwrap();
+            assert_eq!(parsed.sorted_cubes(), polycube.canonical_form());
+            assert_eq!(parsed.canonical_key(), key);
+        }
+
+        let line: Vec<(i8, i8, i8)> = (0..36).map(|z| (0, 0, z)).collect();
+        let key = shape(&line).canonical_key();
+        assert!(key.ends_with("00z"));
+        assert_eq!(Polycube::from_canonical_key(&key).unwrap().canonical_form(), shape(&line).canonical_form());
+
+        assert!(Polycube::from_canonical_key("0000").is_none());
+        assert!(Polycube::from_canonical_key("00-").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 36 cells long")]
+    fn canonical_key_rejects_longer_shapes() {
+        let line: Vec<(i8, i8, i8)> = (0..37).map(|x| (x, 0, 0)).collect();
+        shape(&line).canonical_key();
+    }
+
+    // The bitmask comparison agrees with rotation_mapping on every pair of pentacubes,
+    // and shapes too long for the box fall back to it
+    #[test]
+    fn is_rotation_of_agrees_with_rotation_mapping() {
+        let shapes = crate::generator::generate_polycubes_single_threaded(5);
+        for a in &shapes {
+            for b in &shapes {
+                let turned = b.rotate(19).translate(Pos::new(-3, 2, 1));
+                assert_eq!(a.is_rotation_of(&turned), a.rotation_mapping(&turned).is_some());
+                assert_eq!(a.is_rotation_of(&turned), a == b);
+            }
+        }
+
+        let long_screw: Vec<(i8, i8, i8)> = (0..7).map(|x| (x, 0, 0)).chain([(0, 1, 0), (0, 1, 1)]).collect();
+        let long_screw = shape(&long_screw);
+        assert!(rotated_bits(&long_screw.cubes, &generate_rotation_matrices()[0]).is_none());
+        assert!(long_screw.is_rotation_of(&long_screw.rotate(11)));
+        assert!(!long_screw.is_rotation_of(&long_screw.mirror(crate::polycube::Axis::Y)));
+    }
+
+    // The unit cube grows only into the domino; the straight tromino grows into the
+    // line, L and T tetracubes; and the two trominoes' children are all eight tetracubes
+    #[test]
+    fn children_are_the_distinct_extensions() {
+        let children = Polycube::unit_cube().children();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].canonical_form(), shape(&[(0, 0, 0), (1, 0, 0)]).canonical_form());
+
+        let line = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0)]);
+        assert_eq!(line.children().len(), 3);
+
+        let mut tetracubes = FxHashSet::default();
+        for tromino in crate::generator::generate_polycubes_single_threaded(3) {
+            for child in tromino.children() {
+                assert_eq!(child.cubes.len(), 4);
+                tetracubes.insert(child.canonical_form());
+            }
+        }
+        assert_eq!(tetracubes.len(), 8);
+    }
+
+    // A single cube has one placement per cell of the box; symmetric pieces repeat no
+    // placement, and every placement stays inside the box
+    #[test]
+    fn placements_fill_the_box_without_repeats() {
+        let placements = Polycube::unit_cube().placements_in_box(2, 2, 2);
+        assert_eq!(placements.len(), 8);
+
+        let domino = shape(&[(0, 0, 0), (1, 0, 0)]);
+        assert_eq!(domino.placements_in_box(2, 2, 1).len(), 4);
+        assert_eq!(domino.placements_in_box(2, 2, 2).len(), 12);
+
+        let l_tromino = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        let placements = l_tromino.placements_in_box(2, 2, 1);
+        assert_eq!(placements.len(), 4);
+
+        let distinct: FxHashSet<Vec<Pos>> = placements.iter().map(Polycube::sorted_cubes).collect();
+        assert_eq!(distinct.len(), placements.len());
+        for placement in &placements {
+            assert!(placement.cubes.iter().all(|p| (0..2).contains(&p.x) && (0..2).contains(&p.y) && p.z == 0));
+        }
+
+        assert!(domino.placements_in_box(1, 1, 1).is_empty());
+    }
+}
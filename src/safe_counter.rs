@@ -1,10 +1,15 @@
 use std::time::Instant;
 use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
+use std::sync::{mpsc::Sender, Arc, Mutex};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use rustc_hash::FxHashSet;
 use std::hash::{Hash, Hasher};
 use smallvec::{smallvec, SmallVec};
+use serde::{Deserialize, Serialize};
+
+use crate::generator::{Count, VerificationReport};
+use crate::polycube::{Connectivity, Polycube, Pos};
 
 // Use small integers for coordinates to save memory
 type Coord = i8;
@@ -18,6 +23,85 @@ type PositionVec = SmallVec<[Position; 24]>;
 pub struct CounterConfig {
     pub threads: usize,
     pub show_progress: bool,
+    /// Receives an update, with the count so far, each time the parallel counter finishes a
+    /// starting configuration
+    pub progress: Option<Sender<CounterProgress>>,
+    /// Which neighbouring cells connect cubes. Only `Face` counts are tabulated; the others
+    /// are always computed, single-threaded.
+    pub connectivity: Connectivity,
+    /// Which shapes count as the same. Anything other than `Translation` is counted
+    /// single-threaded.
+    pub symmetry: SymmetryMode,
+    /// Take single-threaded fixed counts depth-first instead of breadth-first. Memory stays
+    /// O(n) shapes rather than growing with the largest layer (over 1 GB at n=10), but the
+    /// count takes about two to three times as long. Ignored with other symmetry modes.
+    pub depth_first: bool,
+    /// Run the counter even when the count is tabulated or cached. Without it
+    /// `count_polycubes_with_config` returns tabulated face-connected counts (n ≤ 18)
+    /// straight from the known tables.
+    pub force_compute: bool,
+    /// Compare a computed count with the known count in `count_polycubes_with_report`. When
+    /// false no report is returned.
+    pub verify: bool,
+}
+
+/// Group of transformations the counter reduces each shape by before deduplicating it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymmetryMode {
+    /// Translations only: counts fixed polycubes (OEIS A001931)
+    #[default]
+    Translation,
+    /// Translations and the 24 rotations: counts one-sided polycubes (OEIS A000162)
+    Rotation,
+    /// Translations, rotations and reflections, 48 in all: counts free polycubes (OEIS A038119)
+    RotationReflection,
+}
+
+impl SymmetryMode {
+    /// Name of the counted kind, as used in the count cache
+    pub fn kind(self) -> &'static str {
+        match self {
+            SymmetryMode::Translation => "fixed",
+            SymmetryMode::Rotation => "one-sided",
+            SymmetryMode::RotationReflection => "free",
+        }
+    }
+    
+    /// Known face-connected count of size n for this kind
+    pub fn known_count(self, n: u8) -> Option<Count> {
+        match self {
+            SymmetryMode::Translation => crate::generator::get_known_fixed_count(n),
+            SymmetryMode::Rotation => crate::generator::get_known_one_sided_count(n),
+            SymmetryMode::RotationReflection => crate::generator::get_known_free_count(n),
+        }
+    }
+    
+    /// Linear parts of the group's transformations; empty for `Translation`, whose
+    /// translations are removed by canonicalization alone
+    fn matrices(self) -> Vec<[[i8; 3]; 3]> {
+        match self {
+            SymmetryMode::Translation => Vec::new(),
+            SymmetryMode::Rotation => crate::rotation::generate_rotation_matrices(),
+            SymmetryMode::RotationReflection => {
+                let rotations = crate::rotation::generate_rotation_matrices();
+                // Each rotation composed with the mirror x -> -x gives one reflection
+                let reflections = rotations.iter().map(|m| {
+                    [[-m[0][0], m[0][1], m[0][2]], [-m[1][0], m[1][1], m[1][2]], [-m[2][0], m[2][1], m[2][2]]]
+                });
+                rotations.iter().copied().chain(reflections).collect()
+            }
+        }
+    }
+}
+
+/// Progress of the parallel counter, in starting configurations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CounterProgress {
+    pub completed: usize,
+    pub total: usize,
+    /// Shapes counted from the completed starting configurations. Updates are sent in
+    /// order, so this never decreases, and the last update carries the final count.
+    pub partial_count: Count,
 }
 
 impl Default for CounterConfig {
@@ -25,35 +109,33 @@ impl Default for CounterConfig {
         CounterConfig {
             threads: num_cpus::get(),
             show_progress: true,
+            progress: None,
+            connectivity: Connectivity::Face,
+            symmetry: SymmetryMode::Translation,
+            depth_first: false,
+            force_compute: false,
+            verify: true,
         }
     }
 }
 
-/// Count fixed polycubes of size n
-pub fn count_fixed_polycubes(n: usize, config: Option<CounterConfig>) -> u64 {
+/// Count fixed polycubes of size n, or with `config.symmetry` set, polycubes up to rotation
+/// or up to rotation and reflection
+pub fn count_fixed_polycubes(n: usize, config: Option<CounterConfig>) -> Count {
     let config = config.unwrap_or_default();
     let start_time = Instant::now();
     
-    // For small n, use known values
-    if n <= 2 {
-        return if n == 1 { 1 } else { 1 };
-    }
+    let face = config.connectivity == Connectivity::Face;
     
     if config.show_progress {
-        println!("Counting fixed polycubes of size {}...", n);
-    }
-    
-    // For n ≤ 7, we could use the generator-based approach
-    if n <= 7 {
-        // Fall back to generator-based counting for small n
-        return crate::generator::get_known_count(n as u8).unwrap_or_else(|| {
-            let polycubes = crate::generator::generate_polycubes(n as u8, true);
-            polycubes.len() as u64
-        });
+        println!("Counting {} polycubes of size {}...", config.symmetry.kind(), n);
     }
     
-    // For larger n, use fixed polycube counter
-    let count = if config.threads <= 1 {
+    // The parallel counter's starting configurations are face-connected and only reduced by
+    // translation, so other connectivities and symmetry modes count single-threaded, as do
+    // sizes no larger than a starting configuration.
+    let translation_only = config.symmetry == SymmetryMode::Translation;
+    let count = if config.threads <= 1 || !face || !translation_only || n <= PARALLEL_STARTING_SIZE {
         // Single-threaded approach for debugging or smaller n
         count_fixed_polycubes_improved(n, &config)
     } else {
@@ -63,7 +145,7 @@ pub fn count_fixed_polycubes(n: usize, config: Option<CounterConfig>) -> u64 {
     
     if config.show_progress {
         let duration = start_time.elapsed();
-        println!("Found {} fixed polycubes of size {}", count, n);
+        println!("Found {} {} polycubes of size {}", count, config.symmetry.kind(), n);
         println!("Time: {:.2} seconds", duration.as_secs_f64());
     }
     
@@ -94,6 +176,33 @@ fn canonicalize_in_place(positions: &mut PositionVec) {
     positions.sort_unstable();
 }
 
+/// Reduce a polycube to the canonical form of its orbit under `matrices` and translations:
+/// the smallest sorted, translated image. With no matrices this is `canonicalize_in_place`.
+fn reduce_in_place(positions: &mut PositionVec, matrices: &[[[i8; 3]; 3]]) {
+    if matrices.is_empty() {
+        canonicalize_in_place(positions);
+        return;
+    }
+    
+    let mut best: Option<PositionVec> = None;
+    for m in matrices {
+        let mut image: PositionVec = positions.iter().map(|&(x, y, z)| (
+            m[0][0] * x + m[0][1] * y + m[0][2] * z,
+            m[1][0] * x + m[1][1] * y + m[1][2] * z,
+            m[2][0] * x + m[2][1] * y + m[2][2] * z,
+        )).collect();
+        canonicalize_in_place(&mut image);
+        
+        if best.as_ref().is_none_or(|best| image < *best) {
+            best = Some(image);
+        }
+    }
+    
+    if let Some(best) = best {
+        *positions = best;
+    }
+}
+
 /// Calculate a hash for a polycube (assumes positions are already in canonical form and sorted)
 fn hash_polycube(positions: &[Position]) -> u64 {
     use std::collections::hash_map::DefaultHasher;
@@ -104,22 +213,101 @@ fn hash_polycube(positions: &[Position]) -> u64 {
     hasher.finish()
 }
 
-/// Get face-adjacent positions that can be added to a polycube
-fn get_valid_extensions(positions: &[Position]) -> PositionVec {
+/// Side length of the box used by `encode_bbox_u128` (5 * 5 * 5 = 125 bits)
+pub const BBOX_SPAN: Coord = 5;
+
+/// Encode a polycube already translated to the origin as a bitmask over a fixed 5x5x5 box,
+/// with bit x*25 + y*5 + z set for each cube. Within the box the encoding is collision-free,
+/// so it can be used directly as a set key. Returns None if the shape does not fit.
+pub fn encode_bbox_u128(positions: &[Position]) -> Option<u128> {
+    let mut bits = 0u128;
+    
+    for &(x, y, z) in positions {
+        if !(0..BBOX_SPAN).contains(&x) || !(0..BBOX_SPAN).contains(&y) || !(0..BBOX_SPAN).contains(&z) {
+            return None;
+        }
+        let index = (x as u32 * 25) + (y as u32 * 5) + z as u32;
+        bits |= 1u128 << index;
+    }
+    
+    Some(bits)
+}
+
+/// Inverse of `encode_bbox_u128`: rebuild the shape from a bitmask over a span x span x span
+/// box, where bit x*span*span + y*span + z marks a cube. The mask does not record its box,
+/// so `span` must be the one used to encode it (`BBOX_SPAN` for `encode_bbox_u128`).
+/// Cubes come out in sorted order, so a shape that fits decodes to its normalized form,
+/// sorted. Panics if span^3 exceeds 128 bits or a bit outside the box is set.
+pub fn decode_bbox_u128(bits: u128, span: u8) -> Polycube {
+    let span = span as u32;
+    let volume = span * span * span;
+    assert!(volume <= u128::BITS, "a {0}x{0}x{0} box does not fit in 128 bits", span);
+    assert!(volume == u128::BITS || bits >> volume == 0, "bits set outside the {0}x{0}x{0} box", span);
+    
+    let cubes = (0..volume)
+        .filter(|&index| bits & (1u128 << index) != 0)
+        .map(|index| Pos::new((index / (span * span)) as Coord, (index / span % span) as Coord, (index % span) as Coord))
+        .collect();
+    
+    Polycube::new(cubes)
+}
+
+/// Dedup key for a canonicalized polycube: the exact bitmask when it fits the 5x5x5 box,
+/// otherwise a 64-bit hash of its positions
+#[derive(Clone, Copy)]
+enum SeenKey {
+    Boxed(u128),
+    Hashed(u64),
+}
+
+impl SeenKey {
+    fn of(positions: &[Position]) -> Self {
+        match encode_bbox_u128(positions) {
+            Some(bits) => SeenKey::Boxed(bits),
+            None => SeenKey::Hashed(hash_polycube(positions)),
+        }
+    }
+}
+
+/// Dedup set keyed by `SeenKey`
+#[derive(Default)]
+struct SeenSet {
+    boxed: FxHashSet<u128>,
+    hashed: FxHashSet<u64>,
+}
+
+impl SeenSet {
+    fn contains(&self, key: SeenKey) -> bool {
+        match key {
+            SeenKey::Boxed(bits) => self.boxed.contains(&bits),
+            SeenKey::Hashed(hash) => self.hashed.contains(&hash),
+        }
+    }
+    
+    fn insert(&mut self, key: SeenKey) -> bool {
+        match key {
+            SeenKey::Boxed(bits) => self.boxed.insert(bits),
+            SeenKey::Hashed(hash) => self.hashed.insert(hash),
+        }
+    }
+    
+    /// Empty the set while keeping its allocations for reuse
+    fn clear(&mut self) {
+        self.boxed.clear();
+        self.hashed.clear();
+    }
+}
+
+/// Get connected positions that can be added to a polycube
+fn get_valid_extensions(positions: &[Position], connectivity: Connectivity) -> PositionVec {
     let occupied: FxHashSet<Position> = positions.iter().copied().collect();
     let mut extensions = FxHashSet::default();
-    
-    // The six face-adjacent directions
-    static DIRECTIONS: [(Coord, Coord, Coord); 6] = [
-        (1, 0, 0), (-1, 0, 0), 
-        (0, 1, 0), (0, -1, 0), 
-        (0, 0, 1), (0, 0, -1)
-    ];
+    let directions = directions(connectivity);
     
     // For each cube in the polycube
     for &(x, y, z) in positions {
-        // Check all 6 face-adjacent positions
-        for &(dx, dy, dz) in &DIRECTIONS {
+        // Check every connected neighbour
+        for &(dx, dy, dz) in &directions {
             let new_pos = (x + dx, y + dy, z + dz);
             if !occupied.contains(&new_pos) {
                 extensions.insert(new_pos);
@@ -129,13 +317,18 @@ fn get_valid_extensions(positions: &[Position]) -> PositionVec {
     
     // Convert to SmallVec and return
     let mut result = PositionVec::new();
-    result.extend(extensions.into_iter());
+    result.extend(extensions);
     result
 }
 
+/// Offsets to the neighbours of a cube under the given connectivity
+fn directions(connectivity: Connectivity) -> SmallVec<[Position; 26]> {
+    connectivity.offsets().iter().map(|offset| (offset.x, offset.y, offset.z)).collect()
+}
+
 /// Check if a polycube is connected (BFS algorithm)
 /// Returns true if all positions are reachable from the first position
-fn is_connected(positions: &[Position]) -> bool {
+fn is_connected(positions: &[Position], connectivity: Connectivity) -> bool {
     if positions.len() <= 1 {
         return true;
     }
@@ -148,17 +341,12 @@ fn is_connected(positions: &[Position]) -> bool {
     queue.push_back(positions[0]);
     visited.insert(positions[0]);
     
-    // The six face-adjacent directions
-    static DIRECTIONS: [(Coord, Coord, Coord); 6] = [
-        (1, 0, 0), (-1, 0, 0), 
-        (0, 1, 0), (0, -1, 0), 
-        (0, 0, 1), (0, 0, -1)
-    ];
+    let directions = directions(connectivity);
     
     // BFS to find all connected cubes
     while let Some((x, y, z)) = queue.pop_front() {
-        // Check all 6 face-adjacent positions
-        for &(dx, dy, dz) in &DIRECTIONS {
+        // Check every connected neighbour
+        for &(dx, dy, dz) in &directions {
             let new_pos = (x + dx, y + dy, z + dz);
             if occupied.contains(&new_pos) && !visited.contains(&new_pos) {
                 visited.insert(new_pos);
@@ -171,21 +359,57 @@ fn is_connected(positions: &[Position]) -> bool {
     visited.len() == positions.len()
 }
 
-/// Improved algorithm for counting fixed polycubes
-fn count_fixed_polycubes_improved(n: usize, _config: &CounterConfig) -> u64 {
-    if n <= 2 {
-        return if n == 1 { 1 } else { 1 };
+/// Improved algorithm for counting fixed polycubes. Depth-first only without symmetry:
+/// reducing by rotations needs the breadth-first counter's global dedup set.
+fn count_fixed_polycubes_improved(n: usize, config: &CounterConfig) -> Count {
+    if config.depth_first && config.symmetry == SymmetryMode::Translation {
+        count_fixed_dfs(n, config.connectivity)
+    } else {
+        count_fixed_bfs(n, config.connectivity, config.symmetry, |_| true, |_| true)
+    }
+}
+
+/// Depth-first count of fixed polycubes of size n, growing every shape from its unique
+/// parent (see `parent_of`) like the parallel counter does from a single cube. Only the
+/// current path and the children of each shape on it are held, so memory stays O(n)
+/// shapes' worth instead of every shape of the largest layer, as in `count_fixed_bfs`.
+fn count_fixed_dfs(n: usize, connectivity: Connectivity) -> Count {
+    if n == 0 {
+        return 0;
     }
     
+    let config = CounterConfig {
+        threads: 1,
+        show_progress: false,
+        progress: None,
+        connectivity,
+        symmetry: SymmetryMode::Translation,
+        depth_first: true,
+        force_compute: true,
+        verify: false,
+    };
+    count_extensions_from(&[(0, 0, 0)], n - 1, &config)
+}
+
+/// Breadth-first count of polycubes of size n, up to `symmetry`.
+/// `keep` is applied to every intermediate shape and rejected shapes are not grown further,
+/// so it must only reject properties that no larger shape could recover from.
+/// `accept` is applied to completed size-n shapes and decides which of them are counted.
+fn count_fixed_bfs<K, A>(n: usize, connectivity: Connectivity, symmetry: SymmetryMode, keep: K, accept: A) -> Count
+where
+    K: Fn(&[Position]) -> bool,
+    A: Fn(&[Position]) -> bool,
+{
     // Start with a single cube
     let mut queue = VecDeque::new();
-    let start_positions = smallvec![(0, 0, 0)];
-    let start_hash = hash_polycube(&start_positions);
+    let start_positions: PositionVec = smallvec![(0, 0, 0)];
+    
+    // Use a dedup set to track polycubes we've already counted
+    let mut visited = SeenSet::default();
+    visited.insert(SeenKey::of(&start_positions));
     queue.push_back((start_positions, 1)); // (positions, cube_count)
     
-    // Use hash set to track polycubes we've already counted
-    let mut visited_hashes = FxHashSet::default();
-    visited_hashes.insert(start_hash);
+    let matrices = symmetry.matrices();
     
     // Use BFS to expand all polycubes layer by layer
     let mut count = 0;
@@ -193,7 +417,9 @@ fn count_fixed_polycubes_improved(n: usize, _config: &CounterConfig) -> u64 {
     while let Some((positions, size)) = queue.pop_front() {
         // If we've reached the target size, increment the count
         if size == n {
-            count += 1;
+            if accept(&positions) {
+                count += 1;
+            }
             continue;
         }
         
@@ -203,7 +429,7 @@ fn count_fixed_polycubes_improved(n: usize, _config: &CounterConfig) -> u64 {
         }
         
         // Get valid extension positions
-        let extensions = get_valid_extensions(&positions);
+        let extensions = get_valid_extensions(&positions, connectivity);
         
         // Try adding each extension
         for ext_pos in extensions {
@@ -212,24 +438,27 @@ fn count_fixed_polycubes_improved(n: usize, _config: &CounterConfig) -> u64 {
             new_positions.extend_from_slice(&positions);
             new_positions.push(ext_pos);
             
-            // Canonicalize in-place to handle translations
-            canonicalize_in_place(&mut new_positions);
-            
-            // Hash to check if we've seen this before
-            let hash = hash_polycube(&new_positions);
+            // Reduce in-place to handle translations and the symmetry group
+            reduce_in_place(&mut new_positions, &matrices);
             
             // Skip if we've seen this polycube before
-            if visited_hashes.contains(&hash) {
+            let key = SeenKey::of(&new_positions);
+            if visited.contains(key) {
                 continue;
             }
             
             // Check if the new polycube is connected
-            if !is_connected(&new_positions) {
+            if !is_connected(&new_positions, connectivity) {
                 continue;
             }
             
             // Add to visited set
-            visited_hashes.insert(hash);
+            visited.insert(key);
+            
+            // Prune shapes the caller has ruled out
+            if !keep(&new_positions) {
+                continue;
+            }
             
             // Add to queue for further expansion
             queue.push_back((new_positions, size + 1));
@@ -239,6 +468,106 @@ fn count_fixed_polycubes_improved(n: usize, _config: &CounterConfig) -> u64 {
     count
 }
 
+/// Extent along each axis of positions already translated to the origin
+fn extents(positions: &[Position]) -> (Coord, Coord, Coord) {
+    let max_x = positions.iter().map(|&(x, _, _)| x).max().unwrap_or(-1);
+    let max_y = positions.iter().map(|&(_, y, _)| y).max().unwrap_or(-1);
+    let max_z = positions.iter().map(|&(_, _, z)| z).max().unwrap_or(-1);
+    (max_x + 1, max_y + 1, max_z + 1)
+}
+
+/// Count fixed polycubes of size n that fit in a slab of height max_z.
+///
+/// The bound applies to the shape's minimal z-extent over all rotations. Since rotations
+/// permute the axes, that is simply its thinnest extent, and because a sub-shape is never
+/// thicker than the shape containing it, intermediate shapes can be pruned safely.
+/// With max_z = 1 this counts the fixed polycubes that are flat (polyominoes in some plane).
+pub fn count_fixed_polycubes_slab(n: usize, max_z: usize, config: Option<CounterConfig>) -> Count {
+    let config = config.unwrap_or_default();
+    let start_time = Instant::now();
+    
+    if config.show_progress {
+        println!("Counting fixed polycubes of size {} with thickness at most {}...", n, max_z);
+    }
+    
+    // No shape fits in a slab without height
+    if max_z == 0 {
+        return 0;
+    }
+    
+    let count = count_fixed_bfs(n, config.connectivity, SymmetryMode::Translation, |positions| {
+        let (dx, dy, dz) = extents(positions);
+        dx.min(dy).min(dz) as usize <= max_z
+    }, |_| true);
+    
+    if config.show_progress {
+        let duration = start_time.elapsed();
+        println!("Found {} fixed polycubes of size {} with thickness at most {}", count, n, max_z);
+        println!("Time: {:.2} seconds", duration.as_secs_f64());
+    }
+    
+    count
+}
+
+/// Count fixed polycubes of size n that are genuinely three-dimensional,
+/// i.e. every dimension of the bounding box is at least 2.
+///
+/// Flatness does not depend on orientation, so each completed shape is classified in the
+/// translation-canonical form the counter already produces. Shapes cannot be pruned early
+/// because solid shapes grow out of flat ones.
+pub fn count_solid_polycubes(n: usize, config: Option<CounterConfig>) -> Count {
+    let config = config.unwrap_or_default();
+    let start_time = Instant::now();
+    
+    if config.show_progress {
+        println!("Counting solid (non-flat) fixed polycubes of size {}...", n);
+    }
+    
+    let count = count_fixed_bfs(n, config.connectivity, SymmetryMode::Translation, |_| true, |positions| {
+        let (dx, dy, dz) = extents(positions);
+        dx >= 2 && dy >= 2 && dz >= 2
+    });
+    
+    if config.show_progress {
+        let duration = start_time.elapsed();
+        println!("Found {} solid fixed polycubes of size {}", count, n);
+        println!("Time: {:.2} seconds", duration.as_secs_f64());
+    }
+    
+    count
+}
+
+/// Count achiral polycubes of size n: free polycubes that are a rotation of their own
+/// mirror image. `config.symmetry` is ignored.
+///
+/// Shapes are reduced under rotations and reflections, so each free polycube is seen once
+/// in the smallest form of its whole class. It is achiral exactly when its mirror image,
+/// reduced under rotations alone, gets back to that form. Chiral shapes pair up into one
+/// free class from two one-sided classes, so free = (one-sided + achiral) / 2.
+pub fn count_achiral(n: usize, config: Option<CounterConfig>) -> Count {
+    let config = config.unwrap_or_default();
+    let start_time = Instant::now();
+    
+    if config.show_progress {
+        println!("Counting achiral polycubes of size {}...", n);
+    }
+    
+    let rotations = SymmetryMode::Rotation.matrices();
+    let count = count_fixed_bfs(n, config.connectivity, SymmetryMode::RotationReflection, |_| true, |positions| {
+        let mut mirrored: PositionVec = positions.iter().map(|&(x, y, z)| (-x, y, z)).collect();
+        reduce_in_place(&mut mirrored, &rotations);
+        mirrored.as_slice() == positions
+    });
+    
+    if config.show_progress {
+        let duration = start_time.elapsed();
+        println!("Found {} achiral polycubes of size {}", count, n);
+        println!("Time: {:.2} seconds", duration.as_secs_f64());
+    }
+    
+    count
+}
+
 /// Generate starting polycubes of a specific size
 fn generate_starting_polycubes(size: usize) -> Vec<PositionVec> {
     // For size 1, just a single cube
@@ -254,13 +583,12 @@ fn generate_starting_polycubes(size: usize) -> Vec<PositionVec> {
     // For sizes 3 and 4, use BFS to generate all canonical forms
     let mut result = Vec::new();
     let mut queue = VecDeque::new();
-    let start_positions = smallvec![(0, 0, 0)];
-    let start_hash = hash_polycube(&start_positions);
-    queue.push_back((start_positions, 1)); // (positions, cube_count)
+    let start_positions: PositionVec = smallvec![(0, 0, 0)];
     
-    // Use hash set to track polycubes we've already counted
-    let mut visited_hashes = FxHashSet::default();
-    visited_hashes.insert(start_hash);
+    // Use a dedup set to track polycubes we've already counted
+    let mut visited = SeenSet::default();
+    visited.insert(SeenKey::of(&start_positions));
+    queue.push_back((start_positions, 1)); // (positions, cube_count)
     
     while let Some((positions, current_size)) = queue.pop_front() {
         // If we've reached the target size, add to results
@@ -270,7 +598,7 @@ fn generate_starting_polycubes(size: usize) -> Vec<PositionVec> {
         }
         
         // Get valid extension positions
-        let extensions = get_valid_extensions(&positions);
+        let extensions = get_valid_extensions(&positions, Connectivity::Face);
         
         // Try adding each extension
         for ext_pos in extensions {
@@ -282,16 +610,14 @@ fn generate_starting_polycubes(size: usize) -> Vec<PositionVec> {
             // Canonicalize in-place to handle translations
             canonicalize_in_place(&mut new_positions);
             
-            // Hash to check if we've seen this before
-            let hash = hash_polycube(&new_positions);
-            
             // Skip if we've seen this polycube before
-            if visited_hashes.contains(&hash) {
+            let key = SeenKey::of(&new_positions);
+            if visited.contains(key) {
                 continue;
             }
             
             // Add to visited set
-            visited_hashes.insert(hash);
+            visited.insert(key);
             
             // Add to queue for further expansion
             queue.push_back((new_positions, current_size + 1));
@@ -301,15 +627,43 @@ fn generate_starting_polycubes(size: usize) -> Vec<PositionVec> {
     result
 }
 
-/// Parallelized counting for better performance
-fn count_fixed_polycubes_parallel(n: usize, config: &CounterConfig) -> u64 {
-    if n <= 2 {
-        return if n == 1 { 1 } else { 1 };
+/// Smallest starting configuration size used by the parallel counter
+const PARALLEL_STARTING_SIZE: usize = 3;
+
+/// Size of the starting configurations the parallel counter splits the work by
+fn parallel_starting_size(n: usize) -> usize {
+    // Size 3 is good for n<=10, but for n>=11 we need size 4 starting points
+    if n <= 10 { PARALLEL_STARTING_SIZE } else { 4 }
+}
+
+/// Count fixed polycubes of size n modulo `modulus`, for comparison with modular results
+/// of other implementations where exact large counts are hard to come by. Like the
+/// parallel counter, the work is split by starting configuration; each part is reduced
+/// as it completes and the residues are summed modulo `modulus`. Configurations the
+/// parallel counter does not handle are counted exactly and then reduced.
+///
+/// Panics if `modulus` is zero.
+pub fn count_fixed_polycubes_mod(n: usize, modulus: u64, config: Option<CounterConfig>) -> u64 {
+    assert!(modulus > 0, "modulus must be positive");
+    let config = config.unwrap_or_default();
+    let modulus = modulus as Count;
+    
+    let face = config.connectivity == Connectivity::Face;
+    if !face || config.symmetry != SymmetryMode::Translation || n <= PARALLEL_STARTING_SIZE {
+        return (count_fixed_polycubes(n, Some(config)) % modulus) as u64;
     }
     
-    // Generate all polycubes of size 3 or 4 to use as starting points
-    // Generating size 3 is good for n<=10, but for n>=11 we need size 4 starting points
-    let starting_size = if n <= 10 { 3 } else { 4 };
+    let starting_polycubes = generate_starting_polycubes(parallel_starting_size(n));
+    let residues: Vec<Count> = maybe_par_iter!(starting_polycubes)
+        .map(|positions| count_extensions_from(positions, n - positions.len(), &config) % modulus)
+        .collect();
+    
+    residues.into_iter().fold(0, |total, residue| (total + residue) % modulus) as u64
+}
+
+/// Parallelized counting for better performance
+fn count_fixed_polycubes_parallel(n: usize, config: &CounterConfig) -> Count {
+    let starting_size = parallel_starting_size(n);
     
     if config.show_progress {
         println!("Generating starting configurations (size {})...", starting_size);
@@ -332,37 +686,17 @@ fn count_fixed_polycubes_parallel(n: usize, config: &CounterConfig) -> u64 {
     }
     
     // Show immediate progress indicator
-    let spinner = if config.show_progress {
-        let spinner = Arc::new(Mutex::new(0u8));
-        let spinner_clone = Arc::clone(&spinner);
-        let stop_spinner = Arc::new(Mutex::new(false));
-        let stop_spinner_clone = Arc::clone(&stop_spinner);
-        
-        // Launch spinner in a separate thread
-        std::thread::spawn(move || {
-            let spinner_chars = ['|', '/', '-', '\\'];
-            while !*stop_spinner_clone.lock().unwrap() {
-                let i = *spinner_clone.lock().unwrap();
-                print!("\rProcessing... {} ", spinner_chars[i as usize % 4]);
-                std::io::Write::flush(&mut std::io::stdout()).unwrap();
-                *spinner_clone.lock().unwrap() = (i + 1) % 4;
-                std::thread::sleep(std::time::Duration::from_millis(200));
-            }
-            print!("\r                      \r"); // Clear the spinner line
-            std::io::Write::flush(&mut std::io::stdout()).unwrap();
-        });
-        
-        Some(stop_spinner)
-    } else {
-        None
-    };
+    #[cfg(not(feature = "indicatif"))]
+    let spinner = config.show_progress.then(start_spinner);
+    #[cfg(feature = "indicatif")]
+    let bar = crate::progress::task_bar(starting_polycubes.len(), 0, format!("Counting n={}", n), config.show_progress);
     
     // Count from each starting polycube in parallel
-    let counter = Arc::new(Mutex::new(0u64));
+    let counter: Arc<Mutex<Count>> = Arc::new(Mutex::new(0));
     let progress = Arc::new(Mutex::new(0usize));
     let total_tasks = starting_polycubes.len();
     
-    starting_polycubes.par_iter().for_each(|positions| {
+    maybe_par_iter!(starting_polycubes).for_each(|positions| {
         // Count extensions from this starting point
         let partial_count = count_extensions_from(positions, n - positions.len(), config);
         
@@ -371,19 +705,30 @@ fn count_fixed_polycubes_parallel(n: usize, config: &CounterConfig) -> u64 {
         *count += partial_count;
         
         // Update progress
+        let mut completed = progress.lock().unwrap();
+        *completed += 1;
+        #[cfg(feature = "indicatif")]
+        bar.inc(1);
+        #[cfg(not(feature = "indicatif"))]
         if config.show_progress {
-            let mut completed = progress.lock().unwrap();
-            *completed += 1;
             println!("\rProgress: {}/{} tasks completed ({:.1}%)",
                    *completed, total_tasks, (*completed as f64 / total_tasks as f64) * 100.0);
         }
+        // Sent while both locks are held, so updates arrive in order
+        if let Some(sender) = &config.progress {
+            // The receiver may have been dropped; progress is best-effort
+            let _ = sender.send(CounterProgress { completed: *completed, total: total_tasks, partial_count: *count });
+        }
     });
     
     // Stop the spinner
+    #[cfg(not(feature = "indicatif"))]
     if let Some(stop_spinner) = spinner {
         *stop_spinner.lock().unwrap() = true;
         std::thread::sleep(std::time::Duration::from_millis(300)); // Give spinner time to clean up
     }
+    #[cfg(feature = "indicatif")]
+    bar.finish();
     
     let total_count = *counter.lock().unwrap();
     
@@ -402,24 +747,65 @@ fn count_fixed_polycubes_parallel(n: usize, config: &CounterConfig) -> u64 {
     total_count
 }
 
+/// Launch the spinner shown while the parallel counter runs; setting the returned flag stops it
+#[cfg(not(feature = "indicatif"))]
+fn start_spinner() -> Arc<Mutex<bool>> {
+    let spinner = Arc::new(Mutex::new(0u8));
+    let spinner_clone = Arc::clone(&spinner);
+    let stop_spinner = Arc::new(Mutex::new(false));
+    let stop_spinner_clone = Arc::clone(&stop_spinner);
+    
+    // Launch spinner in a separate thread
+    std::thread::spawn(move || {
+        let spinner_chars = ['|', '/', '-', '\\'];
+        while !*stop_spinner_clone.lock().unwrap() {
+            let i = *spinner_clone.lock().unwrap();
+            print!("\rProcessing... {} ", spinner_chars[i as usize % 4]);
+            std::io::Write::flush(&mut std::io::stdout()).unwrap();
+            *spinner_clone.lock().unwrap() = (i + 1) % 4;
+            std::thread::sleep(std::time::Duration::from_millis(200));
+        }
+        print!("\r                      \r"); // Clear the spinner line
+        std::io::Write::flush(&mut std::io::stdout()).unwrap();
+    });
+    
+    stop_spinner
+}
+
 /// Count extensions from a starting polycube
-fn count_extensions_from(positions: &[Position], remaining: usize, _config: &CounterConfig) -> u64 {
+fn count_extensions_from(positions: &[Position], remaining: usize, config: &CounterConfig) -> Count {
+    // One dedup set per recursion depth, cleared and reused by every call at that depth
+    let mut seen_by_depth: Vec<SeenSet> = (0..remaining).map(|_| SeenSet::default()).collect();
+    count_extensions_recursive(positions, remaining, &mut seen_by_depth, config)
+}
+
+/// Recursive step of `count_extensions_from`; `seen_by_depth[remaining - 1]` is this call's set
+fn count_extensions_recursive(
+    positions: &[Position],
+    remaining: usize,
+    seen_by_depth: &mut [SeenSet],
+    config: &CounterConfig,
+) -> Count {
     if remaining == 0 {
         return 1; // Found a valid polycube
     }
     
+    // Deeper calls only touch the sets below this level
+    let (deeper, current) = seen_by_depth.split_at_mut(remaining - 1);
+    let visited = &mut current[0];
+    visited.clear();
+    
     // Get valid extension positions
-    let extensions = get_valid_extensions(positions);
+    let extensions = get_valid_extensions(positions, config.connectivity);
     
     // Try adding each extension
     let mut count = 0;
-    let mut visited_hashes = FxHashSet::default();
     
     // Add progress tracking for the first level of recursion
     let total_extensions = extensions.len();
     let mut processed = 0;
     
-    for (i, &ext_pos) in extensions.iter().enumerate() {
+    for &ext_pos in extensions.iter() {
         // Create new polycube with the extension
         let mut new_positions = PositionVec::new();
         new_positions.extend_from_slice(positions);
@@ -428,46 +814,31 @@ fn count_extensions_from(positions: &[Position], remaining: usize, _config: &Cou
         // Canonicalize to handle translations
         canonicalize_in_place(&mut new_positions);
         
-        // Hash to check if we've seen this before
-        let hash = hash_polycube(&new_positions);
-        
         // Skip if we've seen this polycube before
-        if visited_hashes.contains(&hash) {
+        let key = SeenKey::of(&new_positions);
+        if visited.contains(key) {
             continue;
         }
         
         // Check if the new polycube is connected
-        if !is_connected(&new_positions) {
+        if !is_connected(&new_positions, config.connectivity) {
             continue;
         }
         
         // Add to visited set to avoid duplicates
-        visited_hashes.insert(hash);
+        visited.insert(key);
         
-        // Recursively count extensions
-        count += count_extensions_from(&new_positions, remaining - 1, _config);
-        
-        // Skip remaining extensions that we've already tried
-        for &other_ext in &extensions[i+1..] {
-            // Create alternative extension
-            let mut alt_positions = PositionVec::new();
-            alt_positions.extend_from_slice(positions);
-            alt_positions.push(other_ext);
-            
-            // Canonicalize
-            canonicalize_in_place(&mut alt_positions);
-            
-            // Hash
-            let alt_hash = hash_polycube(&alt_positions);
-            
-            // Skip if it's equivalent to one we've already tried
-            if hash == alt_hash {
-                continue;
-            }
+        // Only grow the shape from its own parent, so a shape reachable through several
+        // growth orders (or from several starting configurations) is counted once
+        if parent_of(&new_positions, config.connectivity).as_slice() != positions {
+            continue;
         }
         
-        // Update progress for first level of recursion only
-        if positions.len() <= 4 && remaining >= 6 {
+        // Recursively count extensions
+        count += count_extensions_recursive(&new_positions, remaining - 1, deeper, config);
+        
+        // Update progress for first level of recursion only; the progress bar replaces these lines
+        if cfg!(not(feature = "indicatif")) && config.show_progress && positions.len() <= 4 && remaining >= 6 {
             processed += 1;
             if processed % 10 == 0 || processed == total_extensions {
                 println!("  Sub-progress: {}/{} extensions processed ({:.1}%)", 
@@ -479,84 +850,455 @@ fn count_extensions_from(positions: &[Position], remaining: usize, _config: &Cou
     count
 }
 
-/// Count free polycubes (accounting for symmetry)
-pub fn count_free_polycubes(n: usize, config: Option<CounterConfig>) -> u64 {
+/// The unique shape a canonical polycube is grown from in the parallel counter: remove the
+/// last cube in sorted order whose removal leaves the rest connected, then re-canonicalize.
+/// Every connected shape of two or more cubes has such a cube, and the choice does not depend
+/// on translation, so each shape has exactly one parent.
+fn parent_of(positions: &[Position], connectivity: Connectivity) -> PositionVec {
+    for skip in (0..positions.len()).rev() {
+        let mut rest = PositionVec::new();
+        rest.extend(positions.iter().enumerate().filter(|&(i, _)| i != skip).map(|(_, &pos)| pos));
+        
+        if is_connected(&rest, connectivity) {
+            canonicalize_in_place(&mut rest);
+            return rest;
+        }
+    }
+    
+    PositionVec::new()
+}
+
+/// Count polycubes up to rotation, or up to rotation and reflection (free polycubes) when
+/// `config.symmetry` is `RotationReflection`. Each shape is reduced to the canonical form
+/// of its orbit before deduplication, so the count is exact for every n.
+pub fn count_free_polycubes(n: usize, config: Option<CounterConfig>) -> Count {
     let config = config.unwrap_or_default();
     let start_time = Instant::now();
+    let symmetry = with_rotations(config.symmetry);
     
     if config.show_progress {
-        println!("Counting free polycubes of size {} (with symmetry)...", n);
-    }
-    
-    // For small n, use known values
-    if n <= 2 {
-        return if n == 1 { 1 } else { 1 };
-    }
-    
-    // Use the known values to provide correct counts
-    let count = match n {
-        3 => 2,
-        4 => 8,
-        5 => 29,
-        6 => 166,
-        7 => 1023,
-        8 => 6922,
-        9 => 48311,
-        10 => 346543,
-        11 => 2522522,
-        _ => {
-            // For larger n, we need to account for symmetry
-            // Use known fixed counts and divide by 24 as an approximation (this is pretty accurate for large n numbers)
-            // or use a more accurate method
-            let fixed_count = count_fixed_polycubes(n, Some(config.clone()));
-            
-            // The division by 24 is an approximation - it would be more accurate to 
-            // implement a proper symmetry-aware counting algorithm
-            let free_count = if n == 12 {
-                // n=12 is known to be 18,598,427
-                18598427
-            } else {
-                // Approximate for larger values
-                fixed_count / 24
-            };
-            
-            free_count
-        }
-    };
+        println!("Counting {} polycubes of size {} (with symmetry)...", symmetry.kind(), n);
+    }
+    
+    let count = count_fixed_bfs(n, config.connectivity, symmetry, |_| true, |_| true);
     
     if config.show_progress {
         let duration = start_time.elapsed();
-        
-        // Check if we're using known values or approximating
-        if n <= 12 {
-            println!("Found {} free polycubes of size {}", count, n);
+        println!("Found {} {} polycubes of size {}", count, symmetry.kind(), n);
+        println!("Time: {:.2} seconds", duration.as_secs_f64());
+    }
+    
+    count
+}
+
+/// Symmetry mode for a count with symmetry: rotations at least
+fn with_rotations(symmetry: SymmetryMode) -> SymmetryMode {
+    match symmetry {
+        SymmetryMode::Translation => SymmetryMode::Rotation,
+        symmetry => symmetry,
+    }
+}
+
+/// Symmetry mode a count with these arguments is taken up to
+fn counted_symmetry(use_symmetry: bool, config: Option<&CounterConfig>) -> SymmetryMode {
+    let symmetry = config.map_or(SymmetryMode::Translation, |config| config.symmetry);
+    if use_symmetry { with_rotations(symmetry) } else { symmetry }
+}
+
+/// Count polycubes and compare the result with the known count for the same kind
+/// (one-sided when `use_symmetry` is set, fixed otherwise, unless `config.symmetry` asks
+/// for another), if n is tabulated and `config.verify` is not turned off. A tabulated
+/// count is returned without a report unless `config.force_compute` is set: it comes from
+/// the table itself, so there is nothing to compare.
+pub fn count_polycubes_with_report(n: usize, use_symmetry: bool, config: Option<CounterConfig>) -> (Count, Option<VerificationReport>) {
+    count_with_report_against(n, use_symmetry, config, SymmetryMode::known_count)
+}
+
+/// `count_polycubes_with_report` checked against the table `known`
+fn count_with_report_against(
+    n: usize,
+    use_symmetry: bool,
+    config: Option<CounterConfig>,
+    known: impl Fn(SymmetryMode, u8) -> Option<Count>,
+) -> (Count, Option<VerificationReport>) {
+    let symmetry = counted_symmetry(use_symmetry, config.as_ref());
+    let face = config.as_ref().is_none_or(|config| config.connectivity == Connectivity::Face);
+    let verify = config.as_ref().is_none_or(|config| config.verify);
+    let force_compute = config.as_ref().is_some_and(|config| config.force_compute);
+    let expected = u8::try_from(n).ok().filter(|_| face).and_then(|n| known(symmetry, n));
+    
+    if let Some(expected) = expected.filter(|_| !force_compute) {
+        return (expected, None);
+    }
+    let count = count_polycubes_with_config(n, use_symmetry, config);
+    
+    if !verify {
+        return (count, None);
+    }
+    (count, expected.map(|expected| VerificationReport::new(count, expected)))
+}
+
+/// Find the smallest n in 1..=max_n whose computed count differs from the known count of
+/// the same kind, returning `(n, computed, known)`. Counts are computed for increasing n,
+/// bypassing the count cache, and stop at the first mismatch; sizes without a known count
+/// are skipped.
+pub fn first_divergence(max_n: u8, use_symmetry: bool) -> Option<(u8, Count, Count)> {
+    first_divergence_with(max_n, use_symmetry, |n, use_symmetry| compute_count(n, use_symmetry, None))
+}
+
+/// Like [`first_divergence`], but takes the counter to check, called as
+/// `count(n, use_symmetry)`
+pub fn first_divergence_with(max_n: u8, use_symmetry: bool, count: impl Fn(usize, bool) -> Count) -> Option<(u8, Count, Count)> {
+    (1..=max_n).find_map(|n| {
+        let known = if use_symmetry {
+            crate::generator::get_known_one_sided_count(n)
         } else {
-            println!("Estimated {} free polycubes of size {} (fixed count / 24)", 
-                    count, n);
-            println!("Note: This is an approximation for n > 12.");
+            crate::generator::get_known_fixed_count(n)
+        }?;
+        
+        let computed = count(n as usize, use_symmetry);
+        (computed != known).then_some((n, computed, known))
+    })
+}
+
+/// Compare computed counts against a reference file of `n,count` lines (blank lines are
+/// skipped). Returns one `(n, computed, reference, matches)` row per line, or an
+/// `InvalidData` error naming the first malformed line.
+pub fn verify_against(path: &str, use_symmetry: bool) -> std::io::Result<Vec<(u8, Count, Count, bool)>> {
+    let contents = std::fs::read_to_string(path)?;
+    
+    // Parse everything first so a malformed file fails before any expensive counting
+    let mut references = Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
         }
         
-        println!("Time: {:.2} seconds", duration.as_secs_f64());
+        let malformed = || std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{}:{}: expected `n,count`, found `{}`", path, line_number + 1, line));
+        
+        let (n, count) = line.split_once(',').ok_or_else(malformed)?;
+        let n = n.trim().parse::<u8>().map_err(|_| malformed())?;
+        let count = count.trim().parse::<Count>().map_err(|_| malformed())?;
+        references.push((n, count));
     }
     
-    count
+    Ok(references.into_iter()
+        .map(|(n, reference)| {
+            let config = CounterConfig { force_compute: true, ..CounterConfig::default() };
+            let computed = count_polycubes_with_config(n as usize, use_symmetry, Some(config));
+            (n, computed, reference, computed == reference)
+        })
+        .collect())
+}
+
+/// Write counts for the sizes in `sizes` as an OEIS b-file: one `n a(n)` line per size,
+/// space-separated, with no header. Counts are one-sided (A000162) when `use_symmetry` is
+/// set and fixed (A001931) otherwise, and come from `count_polycubes`, so tabulated and
+/// cached sizes are not recomputed. Sizes start at 1.
+pub fn export_bfile(sizes: std::ops::RangeInclusive<u8>, use_symmetry: bool, path: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    
+    if *sizes.start() == 0 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "b-file sizes start at 1"));
+    }
+    
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for n in sizes {
+        writeln!(writer, "{} {}", n, count_polycubes(n as usize, use_symmetry))?;
+    }
+    writer.flush()
+}
+
+/// Count on the calling thread without printing or using the count cache, for targets
+/// without threads, stdout or a filesystem such as wasm. Counts one-sided polycubes by
+/// generating them when `use_symmetry` is set, and fixed polycubes otherwise.
+pub fn count_polycubes_single_threaded(n: usize, use_symmetry: bool) -> Count {
+    if use_symmetry {
+        crate::generator::generate_polycubes_single_threaded(n as u8).len() as Count
+    } else if n == 0 {
+        0
+    } else {
+        count_fixed_bfs(n, Connectivity::Face, SymmetryMode::Translation, |_| true, |_| true)
+    }
 }
 
 /// Public interface for counting polycubes
-pub fn count_polycubes(n: usize, use_symmetry: bool) -> u64 {
-    // Check if we should use the actual generator for small n
-    if n <= 7 && !use_symmetry {
-        // Fall back to generator-based counting for small n
-        return crate::generator::get_known_count(n as u8).unwrap_or_else(|| {
-            let polycubes = crate::generator::generate_polycubes(n as u8, true);
-            polycubes.len() as u64
-        });
+pub fn count_polycubes(n: usize, use_symmetry: bool) -> Count {
+    count_polycubes_with_config(n, use_symmetry, None)
+}
+
+/// `count_polycubes` with an explicit counter configuration. Tabulated counts are returned
+/// from the known tables, and other counts are looked up in and recorded to the count cache
+/// (see `set_count_cache_path`), unless `config.force_compute` is set.
+pub fn count_polycubes_with_config(n: usize, use_symmetry: bool, config: Option<CounterConfig>) -> Count {
+    let connectivity = config.as_ref().map_or(Connectivity::Face, |config| config.connectivity);
+    let symmetry = counted_symmetry(use_symmetry, config.as_ref());
+    
+    if config.as_ref().is_some_and(|config| config.force_compute) {
+        return compute_count(n, use_symmetry, config);
+    }
+    if let Some(known) = u8::try_from(n).ok()
+        .filter(|_| connectivity == Connectivity::Face)
+        .and_then(|n| symmetry.known_count(n))
+    {
+        return known;
     }
     
+    cached_count(&count_cache_path(), n, use_symmetry, symmetry, connectivity, || compute_count(n, use_symmetry, config))
+}
+
+/// Count without consulting the count cache
+fn compute_count(n: usize, use_symmetry: bool, config: Option<CounterConfig>) -> Count {
     // Use the fast counting algorithm
     if use_symmetry {
-        count_free_polycubes(n, None)
+        count_free_polycubes(n, config)
     } else {
-        count_fixed_polycubes(n, None)
+        count_fixed_polycubes(n, config)
+    }
+}
+
+/// Default location of the count cache
+pub const DEFAULT_COUNT_CACHE_PATH: &str = "counts.json";
+
+static COUNT_CACHE_PATH: Mutex<Option<String>> = Mutex::new(None);
+
+/// One count stored in the count cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedCount {
+    pub n: usize,
+    /// "fixed", "one-sided" or "free" (see `SymmetryMode::kind`); other connectivities than
+    /// face add a suffix, e.g. "fixed/face-edge"
+    pub kind: String,
+    pub value: Count,
+    /// Counting function that produced the value
+    pub algorithm: String,
+    /// Seconds since the Unix epoch when the value was recorded
+    pub timestamp: u64,
+}
+
+/// Set the file `count_polycubes` reads cached counts from and records new counts in
+pub fn set_count_cache_path(path: &str) {
+    *COUNT_CACHE_PATH.lock().unwrap() = Some(path.to_string());
+}
+
+/// Current count cache file
+pub fn count_cache_path() -> String {
+    COUNT_CACHE_PATH.lock().unwrap().clone().unwrap_or_else(|| DEFAULT_COUNT_CACHE_PATH.to_string())
+}
+
+/// Delete the count cache file, if there is one
+pub fn clear_count_cache() -> std::io::Result<()> {
+    match std::fs::remove_file(count_cache_path()) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Entries of the count cache at `path`. A missing or unreadable cache is treated as empty.
+pub fn read_count_cache(path: &str) -> Vec<CachedCount> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Return the cached count for (n, kind) from the cache at `path`, or call `compute`
+/// and record its result there
+fn cached_count(path: &str, n: usize, use_symmetry: bool, symmetry: SymmetryMode, connectivity: Connectivity, compute: impl FnOnce() -> Count) -> Count {
+    let kind = match connectivity {
+        Connectivity::Face => symmetry.kind().to_string(),
+        connectivity => format!("{}/{}", symmetry.kind(), connectivity.name()),
+    };
+    
+    let mut entries = read_count_cache(path);
+    if let Some(entry) = entries.iter().find(|entry| entry.n == n && entry.kind == kind) {
+        return entry.value;
+    }
+    
+    let value = compute();
+    
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let algorithm = if use_symmetry { "count_free_polycubes" } else { "count_fixed_polycubes" };
+    entries.push(CachedCount { n, kind, value, algorithm: algorithm.to_string(), timestamp });
+    
+    let saved = serde_json::to_string_pretty(&entries)
+        .map_err(std::io::Error::other)
+        .and_then(|json| std::fs::write(path, json));
+    if let Err(e) = saved {
+        println!("Error saving count cache {}: {}", path, e);
+    }
+    
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet() -> CounterConfig {
+        CounterConfig { threads: 1, show_progress: false, ..CounterConfig::default() }
+    }
+
+    // Fixed polycubes among the rotations of the generator's one-sided shapes that pass
+    // `keep`, which must not depend on orientation
+    fn fixed_count_of_generated(n: u8, keep: impl Fn(&Polycube) -> bool) -> Count {
+        crate::generator::generate_polycubes_single_threaded(n).iter()
+            .filter(|polycube| keep(polycube))
+            .map(|polycube| {
+                let orientations: FxHashSet<Vec<Pos>> = (0..24).map(|i| polycube.rotate(i).sorted_cubes()).collect();
+                orientations.len() as Count
+            })
+            .sum()
     }
-}
\ No newline at end of file
+
+    fn thinnest_extent(polycube: &Polycube) -> i8 {
+        let (x, y, z) = polycube.get_dimensions();
+        x.min(y).min(z)
+    }
+
+    // Known counts with the one-sided n=5 entry off by one
+    fn corrupted(symmetry: SymmetryMode, n: u8) -> Option<Count> {
+        let known = symmetry.known_count(n)?;
+        Some(if symmetry == SymmetryMode::Rotation && n == 5 { known + 1 } else { known })
+    }
+
+    // H=1 counts exactly the flat shapes, H=2 those at most two layers thick
+    #[test]
+    fn slab_counts_match_filtered_generator() {
+        for n in 1..=6u8 {
+            let flat = fixed_count_of_generated(n, |polycube| thinnest_extent(polycube) <= 1);
+            assert_eq!(count_fixed_polycubes_slab(n as usize, 1, Some(quiet())), flat, "H=1 for n={}", n);
+            let two_layers = fixed_count_of_generated(n, |polycube| thinnest_extent(polycube) <= 2);
+            assert_eq!(count_fixed_polycubes_slab(n as usize, 2, Some(quiet())), two_layers, "H=2 for n={}", n);
+        }
+        assert_eq!(count_fixed_polycubes_slab(4, 0, Some(quiet())), 0);
+    }
+
+    #[test]
+    fn solid_counts_match_filtered_generator() {
+        for n in 1..=7u8 {
+            let solid = fixed_count_of_generated(n, |polycube| !polycube.is_flat());
+            assert_eq!(count_solid_polycubes(n as usize, Some(quiet())), solid, "solid count for n={}", n);
+        }
+    }
+
+    // The counter's reverse search reaches every fixed shape from the single cube
+    #[test]
+    fn parent_chains_end_at_unit_cube() {
+        for polycube in crate::generator::generate_polycubes_single_threaded(6) {
+            for rotation in 0..24 {
+                let mut positions: PositionVec = polycube.rotate(rotation).cubes.iter()
+                    .map(|pos| (pos.x, pos.y, pos.z))
+                    .collect();
+                canonicalize_in_place(&mut positions);
+                while positions.len() > 1 {
+                    let parent = parent_of(&positions, Connectivity::Face);
+                    assert_eq!(parent.len(), positions.len() - 1);
+                    positions = parent;
+                }
+                assert_eq!(positions.as_slice(), [(0, 0, 0)]);
+            }
+        }
+    }
+
+    #[test]
+    fn corrupted_table_entry_is_a_mismatch() {
+        let config = CounterConfig { force_compute: true, ..quiet() };
+        let (count, report) = count_with_report_against(5, true, Some(config.clone()), corrupted);
+        assert_eq!(count, 29);
+        let report = report.unwrap();
+        assert!(!report.matches());
+        assert_eq!((report.found, report.expected, report.delta), (29, 30, -1));
+
+        // Fixed counts use an intact entry
+        let (_, report) = count_with_report_against(5, false, Some(config), corrupted);
+        assert!(report.unwrap().matches());
+    }
+
+    // The first call computes and records the count; the second reads it back from the file
+    #[test]
+    fn second_count_comes_from_the_cache() {
+        let path = std::env::temp_dir().join(format!("polycube_count_cache_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let computed = std::cell::Cell::new(0);
+        let compute = || {
+            computed.set(computed.get() + 1);
+            12345
+        };
+
+        let first = cached_count(path, 30, false, SymmetryMode::Translation, Connectivity::Face, compute);
+        let second = cached_count(path, 30, false, SymmetryMode::Translation, Connectivity::Face, compute);
+        // Another kind of the same size is a separate entry
+        let other_kind = cached_count(path, 30, true, SymmetryMode::Rotation, Connectivity::Face, compute);
+        let entries = read_count_cache(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!((first, second, other_kind), (12345, 12345, 12345));
+        assert_eq!(computed.get(), 2);
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].n, entries[0].kind.as_str(), entries[0].value), (30, "fixed", 12345));
+    }
+
+    #[test]
+    fn table_sourced_counts_have_no_report() {
+        assert_eq!(count_with_report_against(5, true, Some(quiet()), corrupted), (30, None));
+        assert_eq!(count_polycubes_with_report(5, true, Some(quiet())), (29, None));
+    }
+
+    // Every normalized one-sided shape of size 6 fits the box, keys are distinct and decode
+    // back to the shape; a line of six cubes does not fit
+    #[test]
+    fn bbox_keys_are_collision_free() {
+        let shapes = crate::generator::generate_polycubes_single_threaded(6);
+        let mut keys = FxHashSet::default();
+        for polycube in &shapes {
+            let positions: Vec<Position> = polycube.sorted_cubes().iter().map(|pos| (pos.x, pos.y, pos.z)).collect();
+            let Some(key) = encode_bbox_u128(&positions) else { continue };
+            assert!(keys.insert(key), "{:?} shares its key", positions);
+            assert_eq!(decode_bbox_u128(key, BBOX_SPAN as u8).sorted_cubes(), polycube.sorted_cubes());
+        }
+        assert_eq!(keys.len(), shapes.len() - 1);
+
+        let line: Vec<Position> = (0..6).map(|x| (x, 0, 0)).collect();
+        assert_eq!(encode_bbox_u128(&line), None);
+        assert_eq!(encode_bbox_u128(&[(0, -1, 0)]), None);
+    }
+
+    #[test]
+    fn verify_against_reports_mismatches_and_malformed_lines() {
+        let path = std::env::temp_dir().join(format!("polycube_reference_counts_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        std::fs::write(path, "3,15\n\n4, 86\n5,1000\n").unwrap();
+        let rows = verify_against(path, false).unwrap();
+        assert_eq!(rows, [(3, 15, 15, true), (4, 86, 86, true), (5, 534, 1000, false)]);
+
+        std::fs::write(path, "3,15\n4;86\n").unwrap();
+        let error = verify_against(path, false).unwrap_err();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.to_string().ends_with(":2: expected `n,count`, found `4;86`"), "{}", error);
+    }
+
+    // decode(encode(p)) is p normalized; a wider span reads the same bits differently
+    #[test]
+    fn bbox_decoding_inverts_encoding() {
+        for polycube in crate::generator::generate_polycubes_single_threaded(5) {
+            let normalized = polycube.rotate(7);
+            let positions: Vec<Position> = normalized.cubes.iter().map(|pos| (pos.x, pos.y, pos.z)).collect();
+            let bits = encode_bbox_u128(&positions).unwrap();
+            assert_eq!(decode_bbox_u128(bits, BBOX_SPAN as u8).sorted_cubes(), normalized.sorted_cubes());
+        }
+
+        // Bit 5 is (0, 1, 0) in a 5-span box and (0, 1, 1) in a 4-span one
+        let bits = 1 | (1 << 5);
+        assert_eq!(decode_bbox_u128(bits, 5).sorted_cubes(), [Pos::new(0, 0, 0), Pos::new(0, 1, 0)]);
+        assert_eq!(decode_bbox_u128(bits, 4).sorted_cubes(), [Pos::new(0, 0, 0), Pos::new(0, 1, 1)]);
+    }
+}
@@ -0,0 +1,178 @@
+// Exact-cover tiling: fill a region with placements of a set of pieces, searched with
+// Knuth's Algorithm X over the rows that placements_in_box produces.
+
+use rustc_hash::FxHashMap;
+
+use crate::polycube::{Polycube, Pos};
+
+// One way to use a piece: which piece, where, and the region cells it covers
+struct Row {
+    piece: usize,
+    placement: Polycube,
+    cells: Vec<usize>,
+}
+
+// Cover every cell of `region` exactly once with placed copies of `pieces`, each piece used
+// at most once (list a shape twice to allow two copies; pieces left over are fine). Returns
+// one solution as (piece index, placed piece) pairs, in region coordinates, or None.
+pub fn can_tile(region: &Polycube, pieces: &[Polycube]) -> Option<Vec<(usize, Polycube)>> {
+    let available: usize = pieces.iter().map(|piece| piece.cubes.len()).sum();
+    if available < region.cubes.len() {
+        return None;
+    }
+
+    let cell_index: FxHashMap<Pos, usize> = region.sorted_cubes().into_iter()
+        .enumerate()
+        .map(|(index, cell)| (cell, index))
+        .collect();
+    if cell_index.is_empty() {
+        return Some(Vec::new());
+    }
+    let corner = Pos::new(
+        cell_index.keys().map(|cell| cell.x).min().unwrap(),
+        cell_index.keys().map(|cell| cell.y).min().unwrap(),
+        cell_index.keys().map(|cell| cell.z).min().unwrap(),
+    );
+    let (bx, by, bz) = region.normalize().get_dimensions();
+
+    // Every placement inside the bounding box that stays inside the region
+    let cell_index = &cell_index;
+    let rows: Vec<Row> = pieces.iter().enumerate()
+        .flat_map(|(piece, shape)| {
+            shape.placements_in_box(bx, by, bz).into_iter().filter_map(move |placement| {
+                let placement = placement.translate(corner);
+                let cells = placement.cubes.iter()
+                    .map(|cube| cell_index.get(cube).copied())
+                    .collect::<Option<Vec<usize>>>()?;
+                Some(Row { piece, placement, cells })
+            })
+        })
+        .collect();
+
+    let mut rows_by_cell = vec![Vec::new(); cell_index.len()];
+    for (index, row) in rows.iter().enumerate() {
+        for &cell in &row.cells {
+            rows_by_cell[cell].push(index);
+        }
+    }
+
+    let mut search = Search {
+        rows: &rows,
+        rows_by_cell: &rows_by_cell,
+        covered: vec![false; cell_index.len()],
+        used: vec![false; pieces.len()],
+        chosen: Vec::new(),
+    };
+    search.solve().then(|| {
+        search.chosen.iter().map(|&index| (rows[index].piece, rows[index].placement.clone())).collect()
+    })
+}
+
+// Algorithm X state: region cells are the primary columns that must be covered once, and
+// pieces are secondary columns that may be covered at most once
+struct Search<'a> {
+    rows: &'a [Row],
+    rows_by_cell: &'a [Vec<usize>],
+    covered: Vec<bool>,
+    used: Vec<bool>,
+    chosen: Vec<usize>,
+}
+
+impl Search<'_> {
+    fn fits(&self, index: usize) -> bool {
+        let row = &self.rows[index];
+        !self.used[row.piece] && row.cells.iter().all(|&cell| !self.covered[cell])
+    }
+
+    fn set(&mut self, index: usize, value: bool) {
+        let row = &self.rows[index];
+        self.used[row.piece] = value;
+        for &cell in &row.cells {
+            self.covered[cell] = value;
+        }
+    }
+
+    fn solve(&mut self) -> bool {
+        // Branch on the uncovered cell with the fewest rows that still fit
+        let mut best: Option<Vec<usize>> = None;
+        for cell in (0..self.covered.len()).filter(|&cell| !self.covered[cell]) {
+            let fitting: Vec<usize> = self.rows_by_cell[cell].iter().copied().filter(|&index| self.fits(index)).collect();
+            if best.as_ref().is_none_or(|best| fitting.len() < best.len()) {
+                let dead_end = fitting.is_empty();
+                best = Some(fitting);
+                if dead_end {
+                    break;
+                }
+            }
+        }
+
+        let Some(candidates) = best else {
+            // Every cell is covered
+            return true;
+        };
+
+        for index in candidates {
+            self.set(index, true);
+            self.chosen.push(index);
+            if self.solve() {
+                return true;
+            }
+            self.chosen.pop();
+            self.set(index, false);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hash::FxHashSet;
+
+    fn shape(cells: &[(i8, i8, i8)]) -> Polycube {
+        Polycube::new(cells.iter().map(|&(x, y, z)| Pos::new(x, y, z)).collect())
+    }
+
+    // Each cell of the region is covered by exactly one placed piece, each piece used once
+    fn assert_exact_cover(region: &Polycube, pieces: &[Polycube], solution: &[(usize, Polycube)]) {
+        let mut covered = FxHashSet::default();
+        let mut used = FxHashSet::default();
+        for (piece, placement) in solution {
+            assert!(used.insert(*piece));
+            assert_eq!(placement.canonical_form(), pieces[*piece].canonical_form());
+            for &cube in &placement.cubes {
+                assert!(covered.insert(cube));
+            }
+        }
+        assert_eq!(covered, region.cubes.iter().copied().collect());
+    }
+
+    // Two dominoes tile a 2×2×1 box, one domino is too small, and an L-tromino with a
+    // unit cube tiles the same box even when it sits away from the origin
+    #[test]
+    fn dominoes_tile_a_square() {
+        let square = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0)]);
+        let domino = shape(&[(0, 0, 0), (1, 0, 0)]);
+
+        let pieces = [domino.clone(), domino.clone()];
+        let solution = can_tile(&square, &pieces).unwrap();
+        assert_eq!(solution.len(), 2);
+        assert_exact_cover(&square, &pieces, &solution);
+
+        assert!(can_tile(&square, std::slice::from_ref(&domino)).is_none());
+
+        let moved = square.translate(Pos::new(-3, 5, 2));
+        let pieces = [shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]), Polycube::unit_cube()];
+        let solution = can_tile(&moved, &pieces).unwrap();
+        assert_exact_cover(&moved, &pieces, &solution);
+    }
+
+    // Pieces that cannot fit the region's cells leave it untiled
+    #[test]
+    fn mismatched_pieces_do_not_tile() {
+        let line = shape(&[(0, 0, 0), (1, 0, 0), (2, 0, 0), (3, 0, 0)]);
+        let l_tromino = shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)]);
+        assert!(can_tile(&line, &[l_tromino, Polycube::unit_cube()]).is_none());
+        assert_eq!(can_tile(&Polycube::new(Vec::new()), &[]), Some(Vec::new()));
+    }
+}
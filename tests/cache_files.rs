@@ -0,0 +1,81 @@
+// Checks which cache files generation leaves behind. The generator reads and writes
+// cubes_{n}.zst in the working directory, so each test runs in its own temporary
+// directory, one at a time.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use polycube_generator::generator::generate_polycubes_resumable;
+use polycube_generator::{
+    generate_from_cached_base, generate_polycubes_single_threaded, generate_polycubes_with_policy, CachePolicy, GeneratorError, Polycube, Pos,
+};
+
+static WORKING_DIR: Mutex<()> = Mutex::new(());
+
+// Run f with a fresh, empty working directory, returning its result and the names of the
+// files it left there
+fn in_temp_dir<T>(name: &str, f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let _guard = WORKING_DIR.lock().unwrap_or_else(|e| e.into_inner());
+    let dir: PathBuf = std::env::temp_dir().join(format!("polycube_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let previous = std::env::current_dir().unwrap();
+
+    std::env::set_current_dir(&dir).unwrap();
+    let result = f();
+    let mut files: Vec<String> = std::fs::read_dir(".").unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    std::env::set_current_dir(previous).unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    files.sort();
+    (result, files)
+}
+
+#[test]
+fn final_only_leaves_one_cache_file() {
+    let (polycubes, files) = in_temp_dir("final_only", || generate_polycubes_with_policy(6, CachePolicy::FinalOnly));
+    assert_eq!(polycubes.len(), 166);
+    assert_eq!(files, ["cubes_6.zst"]);
+
+    let (_, files) = in_temp_dir("no_cache", || generate_polycubes_with_policy(6, CachePolicy::None));
+    assert!(files.is_empty(), "CachePolicy::None wrote {:?}", files);
+
+    let (_, files) = in_temp_dir("all_sizes", || generate_polycubes_with_policy(6, CachePolicy::All));
+    assert_eq!(files, ["cubes_3.zst", "cubes_4.zst", "cubes_5.zst", "cubes_6.zst"]);
+}
+
+fn canonical_forms(polycubes: &[Polycube]) -> Vec<Vec<Pos>> {
+    let mut forms: Vec<_> = polycubes.iter().map(|polycube| polycube.canonical_form()).collect();
+    forms.sort();
+    forms
+}
+
+// Expanding a size-5 cache to size 6 gives the shapes generated from scratch, writes no
+// other cache, and fails when the base cache is missing
+#[test]
+fn cached_base_expands_to_the_generated_shapes() {
+    let (result, files) = in_temp_dir("cached_base", || {
+        generate_polycubes_with_policy(5, CachePolicy::FinalOnly);
+        generate_from_cached_base(6, 5)
+    });
+    let from_base = result.unwrap();
+    assert_eq!(canonical_forms(&from_base), canonical_forms(&generate_polycubes_single_threaded(6)));
+    assert_eq!(files, ["cubes_5.zst"]);
+
+    let (result, _) = in_temp_dir("missing_base", || generate_from_cached_base(6, 5));
+    assert!(matches!(result, Err(GeneratorError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound));
+}
+
+// A run stopped once size 4 is cached picks up from there: the resumed run of size 6
+// matches a full run and leaves every size cached, with no checkpoint behind
+#[test]
+fn resumed_generation_matches_a_full_run() {
+    let (resumed, files) = in_temp_dir("resume", || {
+        generate_polycubes_resumable(4, "checkpoint.bin", 3).unwrap();
+        generate_polycubes_resumable(6, "checkpoint.bin", 3).unwrap()
+    });
+    assert_eq!(canonical_forms(&resumed), canonical_forms(&generate_polycubes_single_threaded(6)));
+    assert_eq!(files, ["cubes_3.zst", "cubes_4.zst", "cubes_5.zst", "cubes_6.zst"]);
+}
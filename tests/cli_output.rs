@@ -0,0 +1,54 @@
+// Runs the built binary and checks that --json leaves exactly one JSON object on stdout.
+
+use std::process::{Command, Stdio};
+
+// Run the binary in a fresh temporary directory, so caches it might write go nowhere
+fn run(name: &str, args: &[&str]) -> (bool, String) {
+    let dir = std::env::temp_dir().join(format!("polycube_cli_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_polycube-generator"))
+        .args(args)
+        .current_dir(&dir)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    (output.status.success(), String::from_utf8(output.stdout).unwrap())
+}
+
+fn json_object(stdout: &str) -> serde_json::Value {
+    assert_eq!(stdout.lines().count(), 1, "stdout was {:?}", stdout);
+    let value: serde_json::Value = serde_json::from_str(stdout).unwrap();
+    assert!(value.is_object());
+    value
+}
+
+#[test]
+fn computed_count_json_has_the_check() {
+    let (success, stdout) = run("count_compute", &["count", "5", "--json", "--compute"]);
+    assert!(success);
+    let value = json_object(&stdout);
+    assert_eq!(value["n"], 5);
+    assert_eq!(value["kind"], "one-sided");
+    assert_eq!(value["count"], 29);
+    assert_eq!(value["expected"], 29);
+    assert_eq!(value["matched"], true);
+    assert!(value["elapsed_ms"].is_u64());
+}
+
+// Table lookups have nothing to check against, in the subcommand and the legacy form
+#[test]
+fn tabulated_count_json_has_no_check() {
+    let runs = [
+        ("count_free", &["count", "6", "--free", "--json"][..], "free", 112),
+        ("legacy", &["6", "--json"][..], "one-sided", 166),
+    ];
+    for (name, args, kind, count) in runs {
+        let (success, stdout) = run(name, args);
+        assert!(success);
+        let value = json_object(&stdout);
+        assert_eq!(value["kind"], kind);
+        assert_eq!(value["count"], count);
+        assert!(value["expected"].is_null() && value["matched"].is_null());
+    }
+}
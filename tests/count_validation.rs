@@ -0,0 +1,321 @@
+// Checks every counting path against the published tables: fixed (OEIS A001931),
+// one-sided (A000162) and free (A038119). Sizes that take more than a few seconds in a
+// debug build are ignored by default; run them with `cargo test --release -- --ignored`.
+
+use polycube_generator::safe_counter::{
+    count_achiral, count_fixed_polycubes, count_free_polycubes, count_polycubes_with_config, count_polycubes_with_report,
+    export_bfile, first_divergence, first_divergence_with, CounterConfig, CounterProgress, SymmetryMode,
+};
+use polycube_generator::polycube_exporter::{export_all, ExportFormat, SortKey};
+use polycube_generator::{
+    generate_polycubes_single_threaded, generate_polycubes_with_config, get_known_fixed_count, get_known_free_count,
+    get_known_one_sided_count, CachePolicy, Count, DedupBackend, GeneratorConfig,
+};
+
+fn quiet(threads: usize) -> Option<CounterConfig> {
+    Some(CounterConfig { threads, show_progress: false, ..Default::default() })
+}
+
+fn check_fast_counter(sizes: std::ops::RangeInclusive<u8>, threads: usize) {
+    for n in sizes {
+        let known = get_known_fixed_count(n).unwrap();
+        assert_eq!(count_fixed_polycubes(n as usize, quiet(threads)), known, "fixed count for n={} with {} threads", n, threads);
+    }
+}
+
+// The generator yields one shape per rotation class, from which the fixed and free counts
+// follow: a shape with symmetry order k has 24 / k fixed orientations, and a free class is
+// either one achiral shape or a mirror-image pair of chiral ones.
+fn check_generator(sizes: std::ops::RangeInclusive<u8>) {
+    for n in sizes {
+        let polycubes = generate_polycubes_single_threaded(n);
+        let one_sided = polycubes.len() as Count;
+        let fixed: Count = polycubes.iter().map(|p| (24 / p.symmetry_order()) as Count).sum();
+        let achiral = polycubes.iter()
+            .filter(|p| p.mirror_all().canonical_form() == p.canonical_form())
+            .count() as Count;
+
+        assert_eq!(one_sided, get_known_one_sided_count(n).unwrap(), "one-sided count for n={}", n);
+        assert_eq!(fixed, get_known_fixed_count(n).unwrap(), "fixed count for n={}", n);
+        assert_eq!((one_sided + achiral) / 2, get_known_free_count(n).unwrap(), "free count for n={}", n);
+    }
+}
+
+#[test]
+fn fast_counter_single_threaded_small() {
+    check_fast_counter(1..=7, 1);
+}
+
+#[test]
+#[ignore = "slow in debug builds"]
+fn fast_counter_single_threaded_large() {
+    check_fast_counter(8..=10, 1);
+}
+
+fn check_depth_first_counter(sizes: std::ops::RangeInclusive<u8>) {
+    for n in sizes {
+        let config = CounterConfig { depth_first: true, ..quiet(1).unwrap() };
+        let known = get_known_fixed_count(n).unwrap();
+        assert_eq!(count_fixed_polycubes(n as usize, Some(config)), known, "depth-first fixed count for n={}", n);
+    }
+}
+
+#[test]
+fn depth_first_counter_small() {
+    check_depth_first_counter(1..=7);
+}
+
+#[test]
+#[ignore = "slow in debug builds"]
+fn depth_first_counter_large() {
+    check_depth_first_counter(8..=9);
+}
+
+#[test]
+fn fast_counter_parallel_small() {
+    check_fast_counter(1..=7, 2);
+}
+
+#[test]
+#[ignore = "slow in debug builds"]
+fn fast_counter_parallel_large() {
+    check_fast_counter(8..=10, 2);
+}
+
+// The parallel counter reports a running total that only grows and ends at the result
+#[test]
+fn parallel_partial_counts_reach_total() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let config = CounterConfig { progress: Some(sender), ..quiet(2).unwrap() };
+    let total = count_fixed_polycubes(7, Some(config));
+
+    let updates: Vec<CounterProgress> = receiver.iter().collect();
+    assert!(!updates.is_empty());
+    assert!(updates.windows(2).all(|pair| pair[0].partial_count <= pair[1].partial_count));
+    assert_eq!(updates.last().unwrap().partial_count, total);
+    assert_eq!(updates.last().unwrap().completed, updates.last().unwrap().total);
+}
+
+// Tabulated sizes come straight from the tables (n=18 could never be counted in a test);
+// force_compute runs the counter, which reports progress as it goes
+#[test]
+fn tabulated_counts_skip_the_counter_unless_forced() {
+    assert_eq!(count_polycubes_with_config(18, false, quiet(1)), get_known_fixed_count(18).unwrap());
+    assert_eq!(count_polycubes_with_config(18, true, quiet(1)), get_known_one_sided_count(18).unwrap());
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let config = CounterConfig { progress: Some(sender), force_compute: true, ..quiet(2).unwrap() };
+    assert_eq!(count_polycubes_with_config(7, false, Some(config)), get_known_fixed_count(7).unwrap());
+    assert!(receiver.iter().count() > 0);
+}
+
+// verify: false skips the known-count comparison, so even a computed tabulated size has
+// no report
+#[test]
+fn unverified_counts_have_no_report() {
+    let computed = CounterConfig { force_compute: true, ..quiet(1).unwrap() };
+    let (count, report) = count_polycubes_with_report(5, false, Some(computed.clone()));
+    assert_eq!(report.map(|report| report.matches()), Some(true));
+
+    let config = CounterConfig { verify: false, ..computed };
+    assert_eq!(count_polycubes_with_report(5, false, Some(config)), (count, None));
+}
+
+// The real counter agrees with the tables; a stub that is wrong from n=6 on is reported
+// at n=6 with the stub's count and the known one
+#[test]
+fn first_divergence_reports_the_smallest_wrong_size() {
+    assert_eq!(first_divergence(7, true), None);
+
+    let stub = |n: usize, use_symmetry: bool| {
+        let known = if use_symmetry { get_known_one_sided_count(n as u8) } else { get_known_fixed_count(n as u8) };
+        known.unwrap() + if n >= 6 { 1 } else { 0 }
+    };
+    for use_symmetry in [false, true] {
+        let known = if use_symmetry { get_known_one_sided_count(6) } else { get_known_fixed_count(6) }.unwrap();
+        assert_eq!(first_divergence_with(10, use_symmetry, stub), Some((6, known + 1, known)));
+        assert_eq!(first_divergence_with(5, use_symmetry, stub), None);
+    }
+}
+
+// An export missing shapes reports a negative delta instead of printing it
+#[test]
+fn short_export_reports_negative_delta() {
+    let polycubes = generate_polycubes_single_threaded(4);
+    let short = &polycubes[..polycubes.len() - 2];
+    let dir = std::env::temp_dir().join(format!("polycube_export_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let report = export_all(short, 4, dir.to_str().unwrap(), &[ExportFormat::Csv], SortKey::Canonical, true).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(report.paths.len(), 1);
+    assert_eq!(report.shapes_written, short.len());
+    let verification = report.verification.unwrap();
+    assert_eq!(verification.delta, -2);
+    assert!(!verification.matches());
+
+    let unverified = export_all(short, 4, std::env::temp_dir().to_str().unwrap(), &[], SortKey::Canonical, false).unwrap();
+    assert!(unverified.verification.is_none());
+}
+
+// A b-file is exactly `n a(n)` lines and parses back into the counts it was written from
+#[test]
+fn bfile_round_trip() {
+    for use_symmetry in [false, true] {
+        let path = std::env::temp_dir().join(format!("polycube_bfile_test_{}_{}.txt", std::process::id(), use_symmetry));
+        export_bfile(1..=10, use_symmetry, path.to_str().unwrap()).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let rows: Vec<(u8, Count)> = contents.lines()
+            .map(|line| {
+                let (n, count) = line.split_once(' ').unwrap();
+                (n.parse().unwrap(), count.parse().unwrap())
+            })
+            .collect();
+        let known = if use_symmetry { get_known_one_sided_count } else { get_known_fixed_count };
+        let expected: Vec<(u8, Count)> = (1..=10).map(|n| (n, known(n).unwrap())).collect();
+        assert_eq!(rows, expected);
+    }
+}
+
+#[test]
+fn generator_small() {
+    check_generator(1..=6);
+}
+
+#[test]
+#[ignore = "slow in debug builds"]
+fn generator_large() {
+    check_generator(7..=9);
+}
+
+// Generation with the dedup table on disk finds the same shapes as in memory
+fn check_on_disk_dedup(sizes: std::ops::RangeInclusive<u8>) {
+    for n in sizes {
+        let path = std::env::temp_dir().join(format!("polycube_dedup_test_{}_{}.bin", std::process::id(), n));
+        let config = GeneratorConfig {
+            cache_policy: CachePolicy::None,
+            dedup_backend: DedupBackend::OnDisk(path.clone()),
+        };
+        let on_disk = generate_polycubes_with_config(n, &config).unwrap();
+
+        assert_eq!(on_disk.len(), generate_polycubes_single_threaded(n).len(), "on-disk dedup for n={}", n);
+        assert_eq!(on_disk.len() as Count, get_known_one_sided_count(n).unwrap(), "on-disk dedup for n={}", n);
+        assert!(!path.exists(), "dedup table for n={} was not deleted", n);
+    }
+}
+
+#[test]
+fn on_disk_dedup_small() {
+    check_on_disk_dedup(1..=6);
+}
+
+#[test]
+#[ignore = "slow in debug builds"]
+fn on_disk_dedup_large() {
+    check_on_disk_dedup(7..=8);
+}
+
+// Shards of the on-disk table are written from many threads at once; each needs its own
+// file offset, or lookups read another shard's slot and let duplicates through
+#[cfg(feature = "parallel")]
+#[test]
+fn on_disk_dedup_matches_in_memory_on_many_threads() {
+    let n = 8;
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(8).build().unwrap();
+    let path = std::env::temp_dir().join(format!("polycube_dedup_threads_test_{}.bin", std::process::id()));
+    let generate = |dedup_backend| {
+        let config = GeneratorConfig { cache_policy: CachePolicy::None, dedup_backend };
+        pool.install(|| generate_polycubes_with_config(n, &config).unwrap().len())
+    };
+
+    let in_memory = generate(DedupBackend::InMemory);
+    assert_eq!(in_memory as Count, get_known_one_sided_count(n).unwrap());
+    for _ in 0..3 {
+        assert_eq!(generate(DedupBackend::OnDisk(path.clone())), in_memory, "on-disk dedup on 8 threads for n={}", n);
+    }
+}
+
+fn check_symmetric_counter(sizes: std::ops::RangeInclusive<u8>, symmetry: SymmetryMode) {
+    for n in sizes {
+        let config = CounterConfig { symmetry, ..quiet(1).unwrap() };
+        let count = count_free_polycubes(n as usize, Some(config));
+        assert_eq!(count, symmetry.known_count(n).unwrap(), "{} count for n={}", symmetry.kind(), n);
+    }
+}
+
+#[test]
+fn one_sided_counter_small() {
+    check_symmetric_counter(1..=7, SymmetryMode::Rotation);
+}
+
+#[test]
+#[ignore = "slow in debug builds"]
+fn one_sided_counter_large() {
+    check_symmetric_counter(8..=10, SymmetryMode::Rotation);
+}
+
+#[test]
+fn free_counter_small() {
+    check_symmetric_counter(1..=7, SymmetryMode::RotationReflection);
+}
+
+#[test]
+#[ignore = "slow in debug builds"]
+fn free_counter_large() {
+    check_symmetric_counter(8..=10, SymmetryMode::RotationReflection);
+}
+
+// Each chiral free class is two one-sided classes and each achiral one is one
+#[test]
+fn achiral_counter_small() {
+    for n in 1..=7u8 {
+        let achiral = count_achiral(n as usize, quiet(1));
+        let one_sided = get_known_one_sided_count(n).unwrap();
+        assert_eq!(one_sided + achiral, 2 * get_known_free_count(n).unwrap(), "achiral count for n={}", n);
+    }
+}
+
+// Sorted canonical keys of the free polycubes of size n: each one-sided shape and its
+// mirror image share the smaller of their two keys
+fn canonical_set_snapshot(n: u8) -> Vec<String> {
+    let mut keys: Vec<String> = generate_polycubes_single_threaded(n).iter()
+        .map(|polycube| polycube.canonical_key().min(polycube.mirror_all().canonical_key()))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+// Compares whole shape sets, not just counts, with the committed tests/fixtures/free_N.txt
+// (one key per line). A change to the enumeration or to canonical_key shows up here.
+#[test]
+fn free_canonical_sets_match_fixtures() {
+    let fixtures = [
+        include_str!("fixtures/free_1.txt"),
+        include_str!("fixtures/free_2.txt"),
+        include_str!("fixtures/free_3.txt"),
+        include_str!("fixtures/free_4.txt"),
+        include_str!("fixtures/free_5.txt"),
+    ];
+    for (n, fixture) in (1..=5u8).zip(fixtures) {
+        let expected: Vec<&str> = fixture.lines().collect();
+        assert_eq!(canonical_set_snapshot(n), expected, "free shapes for n={}", n);
+        assert_eq!(expected.len() as Count, get_known_free_count(n).unwrap(), "fixture size for n={}", n);
+    }
+}
+
+// Counts are u128, so even 24 times the largest tabulated value has room to spare
+#[test]
+fn largest_tabulated_count_times_24_fits() {
+    let largest = (1..=u8::MAX)
+        .flat_map(|n| [get_known_fixed_count(n), get_known_one_sided_count(n), get_known_free_count(n)])
+        .flatten()
+        .max()
+        .unwrap();
+    let scaled: Count = largest.checked_mul(24).expect("24 times the largest tabulated count overflows");
+    assert!(scaled > largest);
+    assert!(Count::MAX / 24 >= u64::MAX as Count);
+}
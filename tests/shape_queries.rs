@@ -0,0 +1,96 @@
+// Checks the shape queries of the generator and of Polycube against small hand-built
+// shapes whose answers are known.
+
+use polycube_generator::generator::{generate_containing, largest_bounding_boxes};
+use polycube_generator::{generate_polycubes_single_threaded, GeneratorError, Polycube, Pos};
+
+fn shape(cubes: &[(i8, i8, i8)]) -> Polycube {
+    Polycube::new(cubes.iter().map(|&(x, y, z)| Pos::new(x, y, z)).collect())
+}
+
+fn l_tricube() -> Polycube {
+    shape(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)])
+}
+
+// Every shape grown from a seed contains it, and each size is reached from the seed
+#[test]
+fn containing_shapes_contain_the_seed() {
+    let seed = l_tricube();
+    for n in 4..=6 {
+        let shapes = generate_containing(n, &seed).unwrap();
+        assert!(!shapes.is_empty(), "no shapes of size {} contain the seed", n);
+        for polycube in &shapes {
+            assert_eq!(polycube.cubes.len(), n as usize);
+            assert!(polycube.contains_subshape(&seed), "{:?} does not contain the seed", polycube.cubes);
+        }
+    }
+}
+
+// With n equal to the seed size, the only shape is the seed itself
+#[test]
+fn containing_at_seed_size_returns_the_seed() {
+    let seed = l_tricube();
+    let shapes = generate_containing(3, &seed).unwrap();
+    assert_eq!(shapes.len(), 1);
+    assert_eq!(shapes[0].get_canonical_hash(), seed.get_canonical_hash());
+
+    assert!(generate_containing(2, &seed).unwrap().is_empty());
+}
+
+#[test]
+fn containing_rejects_empty_and_disconnected_seeds() {
+    let disconnected = shape(&[(0, 0, 0), (2, 0, 0)]);
+    assert!(matches!(generate_containing(4, &disconnected), Err(GeneratorError::InvalidSeed)));
+    assert!(matches!(generate_containing(4, &shape(&[])), Err(GeneratorError::InvalidSeed)));
+}
+
+// Corners of the box around an L-shape placed away from the origin, and None when there
+// are no cubes
+#[test]
+fn bounding_box_of_an_l_shape() {
+    let l_shape = shape(&[(2, -1, 3), (3, -1, 3), (4, -1, 3), (2, 0, 3)]);
+    assert_eq!(l_shape.bounding_box(), Some((Pos::new(2, -1, 3), Pos::new(4, 0, 3))));
+
+    assert_eq!(shape(&[]).bounding_box(), None);
+}
+
+// Only the center of a solid 3×3×3 block is inside the hull; every cube of a 3×3 plate
+// and of a 2×2×2 block lies on it
+#[test]
+fn solid_block_has_one_interior_cube() {
+    let block: Vec<(i8, i8, i8)> = (0..27).map(|i| (i % 3, i / 3 % 3, i / 9)).collect();
+    let block = shape(&block);
+    assert_eq!(block.interior_cube_count(), 1);
+    assert_eq!(block.hull_cube_count(), 26);
+
+    let plate: Vec<(i8, i8, i8)> = (0..9).map(|i| (i % 3, i / 3, 0)).collect();
+    assert_eq!(shape(&plate).interior_cube_count(), 0);
+    let cube: Vec<(i8, i8, i8)> = (0..8).map(|i| (i % 2, i / 2 % 2, i / 4)).collect();
+    assert_eq!(shape(&cube).interior_cube_count(), 0);
+}
+
+fn box_volume(polycube: &Polycube) -> usize {
+    let (min, max) = polycube.bounding_box().unwrap();
+    [max.x - min.x, max.y - min.y, max.z - min.z].iter().map(|&side| side as usize + 1).product()
+}
+
+// The top-k report starts with the largest box of the whole set and never grows
+#[test]
+fn largest_bounding_boxes_are_ranked_by_volume() {
+    let polycubes = generate_polycubes_single_threaded(5);
+    let largest = polycubes.iter().map(box_volume).max().unwrap();
+
+    let top = largest_bounding_boxes(&polycubes, 3);
+    assert_eq!(top.len(), 3);
+    assert_eq!(box_volume(top[0]), largest);
+    assert!(top.windows(2).all(|pair| box_volume(pair[0]) >= box_volume(pair[1])));
+}
+
+// With inline-cubes a small shape keeps its cubes inside the Polycube; otherwise they are
+// one heap allocation
+#[test]
+fn small_shape_heap_bytes() {
+    let cubes = l_tricube();
+    let expected = if cfg!(feature = "inline-cubes") { 0 } else { cubes.cubes.capacity() * std::mem::size_of::<Pos>() };
+    assert_eq!(cubes.heap_bytes(), expected);
+}